@@ -0,0 +1,148 @@
+use anyhow::{bail, Result};
+
+/// keywords starting a method, lowercase
+const METHOD_KEYWORDS: [&str; 4] = ["процедура", "функция", "procedure", "function"];
+/// keywords ending a method, lowercase
+const METHOD_END_KEYWORDS: [&str; 4] = [
+    "конецпроцедуры",
+    "конецфункции",
+    "endprocedure",
+    "endfunction",
+];
+/// modifier before method keyword (8.3.21+), lowercase
+const ASYNC_KEYWORDS: [&str; 2] = ["асинх", "async"];
+const REGION_KEYWORDS: [&str; 2] = ["#область", "#region"];
+const END_REGION_KEYWORDS: [&str; 2] = ["#конецобласти", "#endregion"];
+
+/// procedure or function with its directives and comments above
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Method<'a> {
+    pub name: &'a str,
+    /// compilation directives and annotations (`&НаСервере`)
+    pub directives: Vec<&'a str>,
+    /// names of enclosing regions, outermost first
+    pub region: Vec<&'a str>,
+    /// full text with line endings
+    pub text: String,
+}
+
+impl Method<'_> {
+    /// methods are matched by name case-insensitively
+    pub fn key(&self) -> String {
+        self.name.to_lowercase()
+    }
+}
+
+/// part of module
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Item<'a> {
+    /// lines outside of methods (variables, regions, module body)
+    Text(String),
+    Method(Method<'a>),
+}
+
+/// `line` without indent starts with one of `keywords` (case-insensitive) followed by non-identifier char,
+/// returns the rest
+fn strip_keyword<'a>(line: &'a str, keywords: &[&str]) -> Option<&'a str> {
+    let line = line.trim_start();
+    keywords.iter().find_map(|keyword| {
+        let len = line
+            .char_indices()
+            .nth(keyword.chars().count())
+            .map_or(line.len(), |(i, _)| i);
+        let (head, rest) = line.split_at(len);
+        let at_boundary = !rest.starts_with(|c: char| c.is_alphanumeric() || c == '_');
+        (head.to_lowercase() == *keyword && at_boundary).then_some(rest)
+    })
+}
+
+/// name of method declared in `line`
+fn method_name(line: &str) -> Option<&str> {
+    let line = strip_keyword(line, &ASYNC_KEYWORDS).unwrap_or(line);
+    let rest = strip_keyword(line, &METHOD_KEYWORDS)?.trim_start();
+    let end = rest.find(|c: char| !(c.is_alphanumeric() || c == '_'))?;
+    (end > 0 && rest[end..].trim_start().starts_with('(')).then(|| &rest[..end])
+}
+
+/// line is attached to next method: directive, annotation or comment
+fn is_preamble(line: &str) -> bool {
+    let line = line.trim_start();
+    line.starts_with('&') || line.starts_with("//")
+}
+
+/// split module into methods and text between them
+pub fn parse(text: &str) -> Result<Vec<Item<'_>>> {
+    let mut items = vec![];
+    // lines not in methods yet, preamble of next method is at the end
+    let mut pending: Vec<&str> = vec![];
+    let mut region: Vec<&str> = vec![];
+    let mut lines = text.split_inclusive('\n').enumerate();
+    while let Some((n, line)) = lines.next() {
+        if let Some(rest) = strip_keyword(line, &REGION_KEYWORDS) {
+            region.push(rest.trim());
+        } else if strip_keyword(line, &END_REGION_KEYWORDS).is_some() {
+            if region.pop().is_none() {
+                bail!("line {}: #EndRegion without #Region", n + 1);
+            }
+        } else if let Some(name) = method_name(line) {
+            let preamble = pending
+                .iter()
+                .rev()
+                .take_while(|line| is_preamble(line))
+                .count();
+            let preamble = pending.split_off(pending.len() - preamble);
+            if !pending.is_empty() {
+                items.push(Item::Text(pending.concat()));
+                pending.clear();
+            }
+            let directives = preamble
+                .iter()
+                .map(|line| line.trim())
+                .filter(|line| line.starts_with('&'))
+                .collect();
+            let mut method_text = preamble.concat();
+            method_text.push_str(line);
+            loop {
+                let Some((_, line)) = lines.next() else {
+                    bail!("line {}: method {name} is not closed", n + 1);
+                };
+                method_text.push_str(line);
+                if strip_keyword(line, &METHOD_END_KEYWORDS).is_some() {
+                    break;
+                }
+            }
+            items.push(Item::Method(Method {
+                name,
+                directives,
+                region: region.clone(),
+                text: method_text,
+            }));
+            continue;
+        }
+        pending.push(line);
+    }
+    if !region.is_empty() {
+        bail!("region {} is not closed", region.join("/"));
+    }
+    if !pending.is_empty() {
+        items.push(Item::Text(pending.concat()));
+    }
+    Ok(items)
+}
+
+/// declaration line of method containing each line of `text` (works for broken modules too)
+pub fn enclosing_methods(text: &str) -> Vec<Option<&str>> {
+    let mut current = None;
+    text.split_inclusive('\n')
+        .map(|line| {
+            if method_name(line).is_some() {
+                current = Some(line.trim());
+            }
+            let method = current;
+            if strip_keyword(line, &METHOD_END_KEYWORDS).is_some() {
+                current = None;
+            }
+            method
+        })
+        .collect()
+}
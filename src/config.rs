@@ -0,0 +1,164 @@
+use serde::Deserialize;
+use std::{
+    collections::HashMap,
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+use log::LevelFilter;
+
+use crate::editor::Backend;
+use crate::encoding::TextEncoding;
+use crate::error::Error;
+use crate::filetype::FileType;
+use crate::profile::Profile;
+
+/// env var with path to config file
+pub const CONFIG_ENV: &str = "VSCODE_ADAPTER_CONFIG";
+/// app directory name in user config dir
+pub const APP_DIR_NAME: &str = "vscode-merge-tool-adapter";
+/// config file name in user config dir
+pub const USER_CONFIG_FILE: &str = "config.toml";
+/// config file name in project dir (or any of its parents)
+pub const PROJECT_CONFIG_FILE: &str = ".vscode-adapter.toml";
+
+/// file used as initial content of merge result
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum MergeSeed {
+    /// %baseCfg
+    #[default]
+    Base,
+    /// %secondCfg
+    Second,
+    /// current content of result (git mergetool `$MERGED`)
+    Merged,
+}
+
+/// adapter defaults from config file
+#[derive(Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Config {
+    /// diff/merge editor
+    pub backend: Option<Backend>,
+    /// path to editor executable
+    pub editor: Option<PathBuf>,
+    /// extra args for editor (before diff/merge args)
+    pub editor_args: Option<Vec<String>>,
+    /// filename extension for syntax highlights of modules
+    pub extension: Option<String>,
+    /// file type by marker in file name from the platform (`".Form" = "xml"`)
+    pub file_types: Option<HashMap<String, FileType>>,
+    /// encoding of merge result (default is same as source)
+    pub output_encoding: Option<TextEncoding>,
+    /// initial content of merge result
+    pub merge_seed: Option<MergeSeed>,
+    /// directory for staging directories
+    pub staging_dir: Option<PathBuf>,
+    /// remove source files
+    pub remove_files: Option<bool>,
+    /// rename source files instead of copy
+    pub rename_files: Option<bool>,
+    /// reopen editor if merge result has conflict markers
+    pub reopen_on_conflicts: Option<bool>,
+    /// merge non-conflicting changes without editor
+    pub auto_merge: Option<bool>,
+    /// merge BSL methods matched by name
+    pub structural: Option<bool>,
+    /// report merge result not changed in editor
+    pub detect_unchanged: Option<bool>,
+    /// exit code for unchanged merge result
+    pub unchanged_exit_code: Option<i32>,
+    /// name of profile from `profiles`
+    pub profile: Option<String>,
+    /// args order of callers and editors
+    pub profiles: Option<HashMap<String, Profile>>,
+    /// directory for log files
+    pub log_dir: Option<PathBuf>,
+    /// least level of logged records (`off` disables log)
+    pub log_level: Option<LevelFilter>,
+}
+
+impl Config {
+    /// read config from file
+    pub fn from_file(path: &Path) -> Result<Self, Error> {
+        let text = fs::read_to_string(path).map_err(|source| Error::ReadConfig {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        toml::from_str(&text).map_err(|source| Error::InvalidConfig {
+            path: path.to_path_buf(),
+            source,
+        })
+    }
+
+    /// override values with values from `other`
+    pub fn merge(self, other: Config) -> Self {
+        Self {
+            backend: other.backend.or(self.backend),
+            editor: other.editor.or(self.editor),
+            editor_args: other.editor_args.or(self.editor_args),
+            extension: other.extension.or(self.extension),
+            file_types: match (self.file_types, other.file_types) {
+                (Some(mut file_types), Some(other)) => {
+                    file_types.extend(other);
+                    Some(file_types)
+                }
+                (file_types, other) => other.or(file_types),
+            },
+            output_encoding: other.output_encoding.or(self.output_encoding),
+            merge_seed: other.merge_seed.or(self.merge_seed),
+            staging_dir: other.staging_dir.or(self.staging_dir),
+            remove_files: other.remove_files.or(self.remove_files),
+            rename_files: other.rename_files.or(self.rename_files),
+            reopen_on_conflicts: other.reopen_on_conflicts.or(self.reopen_on_conflicts),
+            auto_merge: other.auto_merge.or(self.auto_merge),
+            structural: other.structural.or(self.structural),
+            detect_unchanged: other.detect_unchanged.or(self.detect_unchanged),
+            unchanged_exit_code: other.unchanged_exit_code.or(self.unchanged_exit_code),
+            profile: other.profile.or(self.profile),
+            // profiles with the same name are replaced
+            profiles: match (self.profiles, other.profiles) {
+                (Some(mut profiles), Some(other)) => {
+                    profiles.extend(other);
+                    Some(profiles)
+                }
+                (profiles, other) => other.or(profiles),
+            },
+            log_dir: other.log_dir.or(self.log_dir),
+            log_level: other.log_level.or(self.log_level),
+        }
+    }
+
+    /// load config files: per-user, per-project, then `explicit` (`--config`) or from env var
+    pub fn load(explicit: Option<&Path>) -> Result<Self, Error> {
+        let mut config = Config::default();
+        let explicit = explicit
+            .map(Path::to_path_buf)
+            .or_else(|| env::var_os(CONFIG_ENV).map(PathBuf::from));
+        let cwd = env::current_dir().map_err(|source| Error::CurrentDir { source })?;
+        let found = [user_config_path(), project_config_path(&cwd)];
+        for path in found.into_iter().flatten() {
+            config = config.merge(Config::from_file(&path)?);
+        }
+        if let Some(path) = explicit {
+            config = config.merge(Config::from_file(&path)?);
+        }
+        Ok(config)
+    }
+}
+
+/// per-user config, if exists
+pub fn user_config_path() -> Option<PathBuf> {
+    let path = dirs::config_dir()?
+        .join(APP_DIR_NAME)
+        .join(USER_CONFIG_FILE);
+    path.is_file().then_some(path)
+}
+
+/// nearest project config in `dir` or its parents
+pub fn project_config_path(dir: &Path) -> Option<PathBuf> {
+    dir.ancestors()
+        .map(|d| d.join(PROJECT_CONFIG_FILE))
+        .find(|p| p.is_file())
+}
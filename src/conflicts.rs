@@ -0,0 +1,25 @@
+/// line prefixes of conflict markers (git, diff3 and VSCode style)
+pub const CONFLICT_MARKERS: [&str; 3] = ["<<<<<<<", "|||||||", ">>>>>>>"];
+/// separator between sides of conflict
+pub const SEPARATOR_MARKER: &str = "=======";
+
+/// line is conflict marker: marker prefix followed by nothing or space, or bare separator
+fn is_marker(line: &str) -> bool {
+    let line = line.trim_end();
+    line == SEPARATOR_MARKER
+        || CONFLICT_MARKERS
+            .iter()
+            .any(|marker| match line.strip_prefix(marker) {
+                Some(rest) => rest.is_empty() || rest.starts_with(' '),
+                None => false,
+            })
+}
+
+/// line numbers (from 1) of unresolved conflict markers in `text`
+pub fn find_markers(text: &str) -> Vec<usize> {
+    text.lines()
+        .enumerate()
+        .filter(|(_, line)| is_marker(line.trim_start_matches('\u{feff}')))
+        .map(|(i, _)| i + 1)
+        .collect()
+}
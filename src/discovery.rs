@@ -0,0 +1,157 @@
+use std::{
+    env,
+    ffi::OsString,
+    fmt,
+    path::{Path, PathBuf},
+};
+
+use crate::editor::Backend;
+
+/// env var with path to editor executable
+pub const EDITOR_ENV: &str = "VSCODE_ADAPTER_EDITOR";
+
+/// editor executable not found, with everything that was tried
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EditorNotFound {
+    pub tried: Vec<String>,
+}
+
+impl fmt::Display for EditorNotFound {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "editor not found, tried:")?;
+        for item in self.tried.iter() {
+            write!(f, "\n  {item}")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for EditorNotFound {}
+
+/// where to look for editor executable, in order
+#[derive(Debug, Clone, Default)]
+pub struct EditorSearch {
+    /// `--editor` (or `editor` from config), only this one is tried if set
+    pub explicit: Option<PathBuf>,
+    /// value of `EDITOR_ENV`, tried if there is no explicit path
+    pub env: Option<PathBuf>,
+    /// executable names to search in `path_var`
+    pub names: Vec<&'static str>,
+    /// PATH
+    pub path_var: Option<OsString>,
+    /// install locations
+    pub locations: Vec<PathBuf>,
+}
+
+impl EditorSearch {
+    /// search for `backend` in current environment
+    pub fn new(explicit: Option<PathBuf>, backend: Backend) -> Self {
+        Self {
+            explicit,
+            env: env::var_os(EDITOR_ENV).map(PathBuf::from),
+            names: backend.candidates().to_vec(),
+            path_var: env::var_os("PATH"),
+            locations: well_known_locations(backend),
+        }
+    }
+
+    /// first existing executable
+    pub fn find(&self) -> Result<PathBuf, EditorNotFound> {
+        let mut tried = vec![];
+        let cwd = env::current_dir().unwrap_or_default();
+        let resolve = |name: &Path| which::which_in(name, self.path_var.as_ref(), &cwd).ok();
+
+        if let Some(path) = self.explicit.as_deref() {
+            return resolve(path).ok_or_else(|| EditorNotFound {
+                tried: vec![format!("--editor {}", path.display())],
+            });
+        }
+        if let Some(path) = self.env.as_deref() {
+            return resolve(path).ok_or_else(|| EditorNotFound {
+                tried: vec![format!("{EDITOR_ENV}={}", path.display())],
+            });
+        }
+        for name in self.names.iter() {
+            if let Some(path) = resolve(Path::new(name)) {
+                return Ok(path);
+            }
+            tried.push(format!("{name} (PATH)"));
+        }
+        for location in self.locations.iter() {
+            if location.is_file() {
+                return Ok(location.clone());
+            }
+            tried.push(location.display().to_string());
+        }
+        Err(EditorNotFound { tried })
+    }
+}
+
+/// usual install paths of `backend`
+#[cfg(windows)]
+pub fn well_known_locations(backend: Backend) -> Vec<PathBuf> {
+    let local = env::var_os("LOCALAPPDATA").map(|p| PathBuf::from(p).join("Programs"));
+    let program_files =
+        ["ProgramFiles", "ProgramFiles(x86)"].map(|v| env::var_os(v).map(PathBuf::from));
+    let (user, system): (&[&str], &[&str]) = match backend {
+        Backend::Vscode => (
+            &[
+                r"Microsoft VS Code\bin\code.cmd",
+                r"Microsoft VS Code Insiders\bin\code-insiders.cmd",
+            ],
+            &[r"Microsoft VS Code\bin\code.cmd"],
+        ),
+        Backend::Vscodium => (&[r"VSCodium\bin\codium.cmd"], &[r"VSCodium\bin\codium.cmd"]),
+        Backend::Cursor => (&[r"cursor\resources\app\bin\cursor.cmd"], &[]),
+        Backend::Meld => (&[r"Meld\Meld.exe"], &[r"Meld\Meld.exe"]),
+        Backend::Kdiff3 => (&[], &[r"KDiff3\kdiff3.exe", r"KDiff3\bin\kdiff3.exe"]),
+        Backend::P4merge => (&[], &[r"Perforce\p4merge.exe"]),
+    };
+    let mut locations = vec![];
+    if let Some(local) = local {
+        locations.extend(user.iter().map(|p| local.join(p)));
+    }
+    for root in program_files.into_iter().flatten() {
+        locations.extend(system.iter().map(|p| root.join(p)));
+    }
+    locations
+}
+
+/// usual install paths of `backend`
+#[cfg(target_os = "macos")]
+pub fn well_known_locations(backend: Backend) -> Vec<PathBuf> {
+    let paths: &[&str] = match backend {
+        Backend::Vscode => &[
+            "/Applications/Visual Studio Code.app/Contents/Resources/app/bin/code",
+            "/Applications/Visual Studio Code - Insiders.app/Contents/Resources/app/bin/code",
+        ],
+        Backend::Vscodium => &["/Applications/VSCodium.app/Contents/Resources/app/bin/codium"],
+        Backend::Cursor => &["/Applications/Cursor.app/Contents/Resources/app/bin/cursor"],
+        Backend::Meld => &["/Applications/Meld.app/Contents/MacOS/Meld"],
+        Backend::Kdiff3 => &["/Applications/kdiff3.app/Contents/MacOS/kdiff3"],
+        Backend::P4merge => &["/Applications/p4merge.app/Contents/MacOS/p4merge"],
+    };
+    paths.iter().map(PathBuf::from).collect()
+}
+
+/// usual install paths of `backend`
+#[cfg(not(any(windows, target_os = "macos")))]
+pub fn well_known_locations(backend: Backend) -> Vec<PathBuf> {
+    let paths: &[&str] = match backend {
+        Backend::Vscode => &[
+            "/usr/share/code/bin/code",
+            "/snap/bin/code",
+            "/var/lib/flatpak/exports/bin/com.visualstudio.code",
+        ],
+        Backend::Vscodium => &[
+            "/usr/share/codium/bin/codium",
+            "/snap/bin/codium",
+            "/var/lib/flatpak/exports/bin/com.vscodium.codium",
+        ],
+        Backend::Cursor => &["/opt/cursor/cursor", "/usr/share/cursor/bin/cursor"],
+        Backend::Meld => &["/var/lib/flatpak/exports/bin/org.gnome.Meld"],
+        Backend::Kdiff3 => &["/var/lib/flatpak/exports/bin/org.kde.kdiff3"],
+        Backend::P4merge => &["/opt/p4v/bin/p4merge"],
+    };
+    paths.iter().map(PathBuf::from).collect()
+}
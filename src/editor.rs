@@ -0,0 +1,117 @@
+use clap::ValueEnum;
+use serde::Deserialize;
+use std::ffi::OsStr;
+
+/// common args for VSCode
+pub const COMMON_CODE_ARGS: [&str; 4] = ["--new-window", "--sync", "off", "--wait"];
+pub const CODE_CMD_DIFF: &str = "--diff";
+pub const CODE_CMD_MERGE: &str = "--merge";
+/// args for P4Merge (1C exports module text as UTF-8 with BOM)
+pub const P4MERGE_COMMON_ARGS: [&str; 2] = ["-C", "utf8-bom"];
+/// result file option for Meld and KDiff3
+pub const OUTPUT_ARG: &str = "--output";
+
+/// diff/merge editor
+#[derive(ValueEnum, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Backend {
+    /// Visual Studio Code
+    #[default]
+    Vscode,
+    /// VSCodium
+    Vscodium,
+    /// Cursor
+    Cursor,
+    /// Meld
+    Meld,
+    /// KDiff3
+    Kdiff3,
+    /// Perforce P4Merge
+    P4merge,
+}
+
+impl Backend {
+    /// executable names to search in PATH, in order
+    pub fn candidates(&self) -> &'static [&'static str] {
+        match self {
+            Backend::Vscode => &["code", "code-insiders", "codium", "cursor"],
+            Backend::Vscodium => &["codium"],
+            Backend::Cursor => &["cursor"],
+            Backend::Meld => &["meld"],
+            Backend::Kdiff3 => &["kdiff3"],
+            Backend::P4merge => &["p4merge"],
+        }
+    }
+
+    /// args for diff of 2 files, `workspace` folder is opened by VSCode-like editors
+    pub fn diff_args<'a>(
+        &self,
+        workspace: &'a OsStr,
+        base_cfg: &'a OsStr,
+        second_cfg: &'a OsStr,
+    ) -> Vec<&'a OsStr> {
+        let mut args: Vec<&OsStr> = vec![];
+        match self {
+            Backend::Vscode | Backend::Vscodium | Backend::Cursor => {
+                args.extend(COMMON_CODE_ARGS.map(OsStr::new));
+                args.extend([workspace, OsStr::new(CODE_CMD_DIFF)]);
+            }
+            Backend::Meld | Backend::Kdiff3 => {}
+            Backend::P4merge => args.extend(P4MERGE_COMMON_ARGS.map(OsStr::new)),
+        }
+        args.extend([base_cfg, second_cfg]);
+        args
+    }
+
+    /// args for merge of 3 files into `merged`, `workspace` folder is opened by VSCode-like editors
+    pub fn merge_args<'a>(
+        &self,
+        workspace: &'a OsStr,
+        base_cfg: &'a OsStr,
+        second_cfg: &'a OsStr,
+        old_vendor_cfg: &'a OsStr,
+        merged: &'a OsStr,
+    ) -> Vec<&'a OsStr> {
+        let output = OsStr::new(OUTPUT_ARG);
+        let mut args: Vec<&OsStr> = vec![];
+        match self {
+            Backend::Vscode | Backend::Vscodium | Backend::Cursor => {
+                args.extend(COMMON_CODE_ARGS.map(OsStr::new));
+                args.extend([
+                    workspace,
+                    OsStr::new(CODE_CMD_MERGE),
+                    base_cfg,
+                    second_cfg,
+                    old_vendor_cfg,
+                    merged,
+                ]);
+            }
+            // local, base (middle pane is the result), remote
+            Backend::Meld => {
+                args.extend([base_cfg, old_vendor_cfg, second_cfg, output, merged]);
+            }
+            // base, local, remote
+            Backend::Kdiff3 => {
+                args.extend([old_vendor_cfg, base_cfg, second_cfg, output, merged]);
+            }
+            // base, local, remote, result
+            Backend::P4merge => {
+                args.extend(P4MERGE_COMMON_ARGS.map(OsStr::new));
+                args.extend([old_vendor_cfg, base_cfg, second_cfg, merged]);
+            }
+        }
+        args
+    }
+
+    /// editor opens folder with all files to merge in one window (VSCode-like)
+    pub fn opens_workspace(&self) -> bool {
+        matches!(self, Backend::Vscode | Backend::Vscodium | Backend::Cursor)
+    }
+
+    /// args to open `workspace` folder in one window (see `opens_workspace`)
+    pub fn workspace_args<'a>(&self, workspace: &'a OsStr) -> Vec<&'a OsStr> {
+        let mut args: Vec<&OsStr> = COMMON_CODE_ARGS.map(OsStr::new).into();
+        args.push(workspace);
+        args
+    }
+}
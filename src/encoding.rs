@@ -0,0 +1,112 @@
+use clap::ValueEnum;
+use encoding_rs::{UTF_16BE, UTF_16LE, WINDOWS_1251};
+use serde::Deserialize;
+
+pub const BOM_UTF8: &[u8] = b"\xEF\xBB\xBF";
+pub const BOM_UTF16LE: &[u8] = b"\xFF\xFE";
+pub const BOM_UTF16BE: &[u8] = b"\xFE\xFF";
+
+/// text encoding of 1C module files
+#[derive(ValueEnum, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum TextEncoding {
+    /// UTF-8 with BOM (1C export default)
+    #[default]
+    Utf8Bom,
+    /// UTF-8 without BOM
+    Utf8,
+    /// UTF-16 little endian with BOM
+    Utf16le,
+    /// UTF-16 big endian with BOM
+    Utf16be,
+    /// Windows-1251 (older platform versions)
+    Cp1251,
+}
+
+impl TextEncoding {
+    /// can be shown in editor without conversion
+    pub fn is_utf8(&self) -> bool {
+        matches!(self, TextEncoding::Utf8Bom | TextEncoding::Utf8)
+    }
+}
+
+/// guess encoding by BOM, UTF-8 validity and zero bytes of UTF-16
+pub fn detect(bytes: &[u8]) -> TextEncoding {
+    if bytes.starts_with(BOM_UTF8) {
+        return TextEncoding::Utf8Bom;
+    }
+    if bytes.starts_with(BOM_UTF16LE) {
+        return TextEncoding::Utf16le;
+    }
+    if bytes.starts_with(BOM_UTF16BE) {
+        return TextEncoding::Utf16be;
+    }
+    // UTF-16 without BOM: high byte of ASCII chars is 0x00, of Cyrillic ones 0x04 (such bytes are
+    // not in UTF-8 or CP1251 text, which may pass as valid UTF-8 otherwise)
+    let high_at = |parity: usize| {
+        bytes
+            .iter()
+            .skip(parity)
+            .step_by(2)
+            .filter(|b| matches!(**b, 0x00 | 0x04))
+            .count()
+    };
+    let half = bytes.len() / 4;
+    if bytes.len().is_multiple_of(2) && half > 0 {
+        if high_at(1) > half {
+            return TextEncoding::Utf16le;
+        }
+        if high_at(0) > half {
+            return TextEncoding::Utf16be;
+        }
+    }
+    if std::str::from_utf8(bytes).is_ok() {
+        return TextEncoding::Utf8;
+    }
+    TextEncoding::Cp1251
+}
+
+/// decode `bytes` (BOM is removed)
+pub fn decode(bytes: &[u8], encoding: TextEncoding) -> String {
+    let encoding = match encoding {
+        TextEncoding::Utf8Bom | TextEncoding::Utf8 => {
+            let bytes = bytes.strip_prefix(BOM_UTF8).unwrap_or(bytes);
+            return String::from_utf8_lossy(bytes).into_owned();
+        }
+        TextEncoding::Utf16le => UTF_16LE,
+        TextEncoding::Utf16be => UTF_16BE,
+        TextEncoding::Cp1251 => WINDOWS_1251,
+    };
+    let (text, _) = encoding.decode_with_bom_removal(bytes);
+    text.into_owned()
+}
+
+/// encode `text` (BOM is added for `Utf8Bom` and UTF-16)
+pub fn encode(text: &str, encoding: TextEncoding) -> Vec<u8> {
+    let text = text.strip_prefix('\u{feff}').unwrap_or(text);
+    match encoding {
+        TextEncoding::Utf8Bom => [BOM_UTF8, text.as_bytes()].concat(),
+        TextEncoding::Utf8 => text.as_bytes().to_vec(),
+        TextEncoding::Utf16le => {
+            let mut bytes = BOM_UTF16LE.to_vec();
+            bytes.extend(text.encode_utf16().flat_map(u16::to_le_bytes));
+            bytes
+        }
+        TextEncoding::Utf16be => {
+            let mut bytes = BOM_UTF16BE.to_vec();
+            bytes.extend(text.encode_utf16().flat_map(u16::to_be_bytes));
+            bytes
+        }
+        TextEncoding::Cp1251 => WINDOWS_1251.encode(text).0.into_owned(),
+    }
+}
+
+/// convert `bytes` to UTF-8 with BOM for editor, returns source encoding
+pub fn to_editor(bytes: &[u8]) -> (TextEncoding, Option<Vec<u8>>) {
+    let encoding = detect(bytes);
+    if encoding.is_utf8() {
+        return (encoding, None);
+    }
+    let text = decode(bytes, encoding);
+    (encoding, Some(encode(&text, TextEncoding::Utf8Bom)))
+}
@@ -0,0 +1,240 @@
+use std::{fmt, io, path::PathBuf, process::ExitStatus};
+
+use thiserror::Error;
+
+use crate::discovery::EditorNotFound;
+use crate::exit::{EXIT_EDITOR_NOT_FOUND, EXIT_INVALID_ARGS, EXIT_IO_ERROR, EXIT_UNCHANGED};
+use crate::i18n::{tr_in, translate, Lang};
+
+/// failure of diff/merge with the offending paths
+#[derive(Debug, Error)]
+pub enum Error {
+    /// source file from the platform (or staged file) can't be read
+    Read { path: PathBuf, source: io::Error },
+    /// staging directory can't be created
+    Workspace { dir: PathBuf, source: io::Error },
+    /// source file can't be copied, moved or converted into staging directory
+    Stage {
+        from: PathBuf,
+        to: PathBuf,
+        source: io::Error,
+    },
+    /// initial merge result can't be created
+    Seed { path: PathBuf, source: io::Error },
+    /// editor can't be launched
+    Launch { program: PathBuf, source: io::Error },
+    /// editor executable not found
+    EditorNotFound(#[from] EditorNotFound),
+    /// merge result can't be written to the platform's file
+    WriteBack { path: PathBuf, source: io::Error },
+    /// source file moved to staging directory can't be moved back
+    RenameBack {
+        from: PathBuf,
+        to: PathBuf,
+        source: io::Error,
+    },
+    /// rollback failed, staging directory with moved sources is kept
+    StagingKept { dir: PathBuf, source: Box<Error> },
+    /// staged file or staging directory can't be removed
+    Cleanup { path: PathBuf, source: io::Error },
+    /// `--profile` is not in config
+    ProfileNotFound { name: String },
+    /// profile does not list roles of args properly
+    InvalidProfile { roles: String },
+    /// merge journal can't be read or written
+    Journal { path: PathBuf, source: io::Error },
+    /// merge journal is for other directories
+    JournalMismatch { path: PathBuf },
+    /// Ctrl+C was pressed
+    Interrupted,
+    /// config file can't be read
+    ReadConfig { path: PathBuf, source: io::Error },
+    /// config file is not valid TOML or has unknown keys
+    InvalidConfig {
+        path: PathBuf,
+        source: toml::de::Error,
+    },
+    /// current directory (to resolve relative paths) can't be read
+    CurrentDir { source: io::Error },
+    /// path of adapter executable (for git config) can't be read
+    CurrentExe { source: io::Error },
+    /// `git` can't be launched
+    GitLaunch { source: io::Error },
+    /// `git config` exited with error
+    GitConfig { key: String, status: ExitStatus },
+    /// diff can't be printed to stdout
+    Output { source: io::Error },
+    /// `Program::run` is called again
+    AlreadyRun,
+    /// Ctrl+C handler can't be set
+    Interrupts { source: ctrlc::Error },
+    /// log file in `dir` can't be opened (or logger is already set)
+    Log { dir: PathBuf, source: io::Error },
+}
+
+impl Error {
+    /// exit code (see `exit`)
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Error::ProfileNotFound { .. }
+            | Error::InvalidProfile { .. }
+            | Error::JournalMismatch { .. }
+            | Error::ReadConfig { .. }
+            | Error::InvalidConfig { .. }
+            | Error::AlreadyRun => EXIT_INVALID_ARGS,
+            Error::Interrupted => EXIT_UNCHANGED,
+            Error::Launch { .. } | Error::EditorNotFound(_) => EXIT_EDITOR_NOT_FOUND,
+            Error::StagingKept { source, .. } => source.exit_code(),
+            _ => EXIT_IO_ERROR,
+        }
+    }
+
+    /// message in `lang`, with the cause (english one is the key of catalog)
+    pub fn message(&self, lang: Lang) -> String {
+        let io = |err: &io::Error| io_message(err, lang);
+        match self {
+            Error::Read { path, source } => tr_in(
+                lang,
+                "can't read {path}: {cause}",
+                &[("path", &path.display()), ("cause", &io(source))],
+            ),
+            Error::Workspace { dir, source } => tr_in(
+                lang,
+                "can't create staging directory in {dir}: {cause}",
+                &[("dir", &dir.display()), ("cause", &io(source))],
+            ),
+            Error::Stage { from, to, source } => tr_in(
+                lang,
+                "can't stage {from} as {to}: {cause}",
+                &[
+                    ("from", &from.display()),
+                    ("to", &to.display()),
+                    ("cause", &io(source)),
+                ],
+            ),
+            Error::Seed { path, source } => tr_in(
+                lang,
+                "can't create merge result {path}: {cause}",
+                &[("path", &path.display()), ("cause", &io(source))],
+            ),
+            Error::Launch { program, source } => tr_in(
+                lang,
+                "can't launch editor {program}: {cause}",
+                &[("program", &program.display()), ("cause", &io(source))],
+            ),
+            Error::EditorNotFound(err) => {
+                let tried: Vec<String> = err.tried.iter().map(|t| format!("\n  {t}")).collect();
+                let tried = tried.concat();
+                tr_in(
+                    lang,
+                    "editor not found, tried:{tried}",
+                    &[("tried", &tried)],
+                )
+            }
+            Error::WriteBack { path, source } => tr_in(
+                lang,
+                "can't write merge result {path}: {cause}",
+                &[("path", &path.display()), ("cause", &io(source))],
+            ),
+            Error::RenameBack { from, to, source } => tr_in(
+                lang,
+                "can't move {from} back to {to}: {cause}",
+                &[
+                    ("from", &from.display()),
+                    ("to", &to.display()),
+                    ("cause", &io(source)),
+                ],
+            ),
+            Error::StagingKept { dir, source } => tr_in(
+                lang,
+                "{error}, staging directory {dir} is kept",
+                &[("error", &source.message(lang)), ("dir", &dir.display())],
+            ),
+            Error::Cleanup { path, source } => tr_in(
+                lang,
+                "can't remove {path}: {cause}",
+                &[("path", &path.display()), ("cause", &io(source))],
+            ),
+            Error::ProfileNotFound { name } => tr_in(
+                lang,
+                "profile {name} not found in config",
+                &[("name", name)],
+            ),
+            Error::InvalidProfile { roles } => tr_in(
+                lang,
+                "profile must list each of {roles} once",
+                &[("roles", roles)],
+            ),
+            Error::Journal { path, source } => tr_in(
+                lang,
+                "can't use journal {path}: {cause}",
+                &[("path", &path.display()), ("cause", &io(source))],
+            ),
+            Error::JournalMismatch { path } => tr_in(
+                lang,
+                "journal {path} is for other directories",
+                &[("path", &path.display())],
+            ),
+            Error::Interrupted => tr_in(lang, "interrupted", &[]),
+            Error::ReadConfig { path, source } => tr_in(
+                lang,
+                "can't read config {path}: {cause}",
+                &[("path", &path.display()), ("cause", &io(source))],
+            ),
+            Error::InvalidConfig { path, source } => tr_in(
+                lang,
+                "invalid config {path}: {cause}",
+                &[("path", &path.display()), ("cause", source)],
+            ),
+            Error::CurrentDir { source } => tr_in(
+                lang,
+                "can't get current directory: {cause}",
+                &[("cause", &io(source))],
+            ),
+            Error::CurrentExe { source } => tr_in(
+                lang,
+                "can't get path of adapter: {cause}",
+                &[("cause", &io(source))],
+            ),
+            Error::GitLaunch { source } => {
+                tr_in(lang, "can't run git: {cause}", &[("cause", &io(source))])
+            }
+            Error::GitConfig { key, status } => tr_in(
+                lang,
+                "git config {key} failed: {status}",
+                &[("key", key), ("status", status)],
+            ),
+            Error::Output { source } => {
+                tr_in(lang, "can't print diff: {cause}", &[("cause", &io(source))])
+            }
+            Error::AlreadyRun => tr_in(lang, "action is already done", &[]),
+            Error::Interrupts { source } => {
+                tr_in(lang, "can't handle Ctrl+C: {cause}", &[("cause", source)])
+            }
+            Error::Log { dir, source } => tr_in(
+                lang,
+                "can't log to {dir}: {cause}",
+                &[("dir", &dir.display()), ("cause", &io(source))],
+            ),
+        }
+    }
+}
+
+/// english message
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.message(Lang::En))
+    }
+}
+
+/// description of common io errors in `lang`, os message in english
+fn io_message(err: &io::Error, lang: Lang) -> String {
+    let text = match err.kind() {
+        _ if lang == Lang::En => return err.to_string(),
+        io::ErrorKind::NotFound => "file not found",
+        io::ErrorKind::PermissionDenied => "permission denied",
+        io::ErrorKind::AlreadyExists => "file already exists",
+        _ => return err.to_string(),
+    };
+    translate(lang, text).into()
+}
@@ -0,0 +1,27 @@
+use std::process::ExitStatus;
+
+/// diff/merge done (editor closed normally)
+pub const EXIT_SUCCESS: i32 = 0;
+/// files differ (`diff --output`)
+pub const EXIT_DIFFERENT: i32 = 1;
+/// invalid args, config or profile (same as args rejected by clap)
+pub const EXIT_INVALID_ARGS: i32 = 2;
+/// merge result has unresolved conflict markers
+pub const EXIT_CONFLICTS: i32 = 3;
+/// default code if user abandoned merge: result not changed (`--detect-unchanged`) or Ctrl+C
+pub const EXIT_UNCHANGED: i32 = 4;
+/// editor executable not found or can't be launched
+pub const EXIT_EDITOR_NOT_FOUND: i32 = 5;
+/// editor exited with error or was killed by a signal
+pub const EXIT_EDITOR_FAILED: i32 = 6;
+/// file operation failed (and any other error)
+pub const EXIT_IO_ERROR: i32 = 7;
+
+/// exit code for editor `status`, signal is a failure too
+pub fn editor_code(status: ExitStatus) -> i32 {
+    if status.success() {
+        EXIT_SUCCESS
+    } else {
+        EXIT_EDITOR_FAILED
+    }
+}
@@ -0,0 +1,161 @@
+use serde::Deserialize;
+
+use crate::staging::BSL_LANGUAGE_ID;
+
+/// language id of query text in VSCode (1C extension)
+pub const SDBL_LANGUAGE_ID: &str = "sdbl";
+
+/// kind of text the platform compares
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum FileType {
+    /// module (1C:Enterprise script)
+    Bsl,
+    /// form, DCS schema, metadata object
+    Xml,
+    /// help page
+    Html,
+    /// query text
+    Sdbl,
+    /// template or anything else
+    Txt,
+}
+
+impl FileType {
+    /// filename extension of staged files, `bsl` is the configured extension
+    pub fn extension<'a>(&self, bsl: &'a str) -> &'a str {
+        match self {
+            FileType::Bsl => bsl,
+            FileType::Xml => "xml",
+            FileType::Html => "html",
+            FileType::Sdbl => "sdbl",
+            FileType::Txt => "txt",
+        }
+    }
+
+    /// VSCode language of staged files if editor does not know the extension
+    pub fn language_id(&self) -> Option<&'static str> {
+        match self {
+            FileType::Bsl => Some(BSL_LANGUAGE_ID),
+            FileType::Sdbl => Some(SDBL_LANGUAGE_ID),
+            _ => None,
+        }
+    }
+}
+
+/// markers in names of files from the platform: whole segments between dots (case-insensitive),
+/// `*` at start or end of segment matches any prefix or suffix
+pub const DEFAULT_NAME_RULES: [(&str, FileType); 9] = [
+    (".Form", FileType::Xml),
+    (".DataCompositionSchema", FileType::Xml),
+    (".Help", FileType::Html),
+    (".Template", FileType::Txt),
+    (".Query", FileType::Sdbl),
+    (".*Module", FileType::Bsl),
+    (".Модуль*", FileType::Bsl),
+    (".Форма", FileType::Xml),
+    (".Справка", FileType::Html),
+];
+
+/// lowercase segments of `name` without extension and ` (File …)` suffix
+fn name_segments(name: &str) -> Vec<String> {
+    let name = name.to_lowercase();
+    let stem = name
+        .rsplit_once('.')
+        .map_or(name.as_str(), |(stem, _)| stem);
+    let stem = match stem.rsplit_once(" (") {
+        Some((stem, suffix)) if suffix.ends_with(')') => stem,
+        _ => stem,
+    };
+    stem.split('.').map(str::to_owned).collect()
+}
+
+/// `segment` of name matches `pattern` segment of marker (both lowercase)
+fn segment_matches(segment: &str, pattern: &str) -> bool {
+    if let Some(suffix) = pattern.strip_prefix('*') {
+        segment.ends_with(suffix)
+    } else if let Some(prefix) = pattern.strip_suffix('*') {
+        segment.starts_with(prefix)
+    } else {
+        segment == pattern
+    }
+}
+
+/// index of last segment of the rightmost match of `marker` in `segments`
+fn marker_end(segments: &[String], marker: &str) -> Option<usize> {
+    let marker = marker.to_lowercase();
+    let patterns: Vec<&str> = marker.trim_start_matches('.').split('.').collect();
+    segments
+        .windows(patterns.len())
+        .rposition(|window| {
+            window
+                .iter()
+                .zip(&patterns)
+                .all(|(segment, pattern)| segment_matches(segment, pattern))
+        })
+        .map(|start| start + patterns.len() - 1)
+}
+
+/// type by marker in `name`: the last matching segment decides, then the longest marker
+fn by_name(name: &str, rules: &[(String, FileType)]) -> Option<FileType> {
+    let segments = name_segments(name);
+    rules
+        .iter()
+        .filter_map(|(marker, file_type)| {
+            let end = marker_end(&segments, marker)?;
+            Some(((end, marker.split('.').count()), *file_type))
+        })
+        .max_by_key(|(rank, _)| *rank)
+        .map(|(_, file_type)| file_type)
+}
+
+/// starts with `prefix`, ignoring case
+fn starts_with_ignore_case(text: &str, prefix: &str) -> bool {
+    text.chars()
+        .take(prefix.chars().count())
+        .flat_map(char::to_lowercase)
+        .eq(prefix.chars().flat_map(char::to_lowercase))
+}
+
+/// starts with query `keyword` (not a variable or a call), ignoring case
+fn starts_with_keyword(text: &str, keyword: &str) -> bool {
+    let rest: String = text.chars().skip(keyword.chars().count()).collect();
+    let followed_by_space = rest.is_empty() || rest.starts_with(char::is_whitespace);
+    let statement = rest.trim_start().starts_with(['=', '(', ';', '.']);
+    starts_with_ignore_case(text, keyword) && followed_by_space && !statement
+}
+
+/// type by content, `None` if not sure
+fn by_content(text: &str) -> Option<FileType> {
+    let text = text.trim_start_matches('\u{feff}').trim_start();
+    let first_code = text
+        .lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty() && !line.starts_with("//"))
+        .unwrap_or_default();
+    let html = ["<!doctype html", "<html"];
+    if html.iter().any(|p| starts_with_ignore_case(text, p)) {
+        Some(FileType::Html)
+    } else if text.starts_with("<?xml") || text.starts_with('<') {
+        Some(FileType::Xml)
+    } else if ["ВЫБРАТЬ", "SELECT", "УНИЧТОЖИТЬ", "DROP"]
+        .iter()
+        .any(|p| starts_with_keyword(first_code, p))
+    {
+        Some(FileType::Sdbl)
+    } else {
+        None
+    }
+}
+
+/// type of file by name (`rules` first, then default rules) and by content, module if unknown
+pub fn classify(name: &str, text: &str, rules: &[(String, FileType)]) -> FileType {
+    let defaults: Vec<(String, FileType)> = DEFAULT_NAME_RULES
+        .iter()
+        .map(|(marker, file_type)| (marker.to_string(), *file_type))
+        .collect();
+    by_name(name, rules)
+        .or_else(|| by_name(name, &defaults))
+        .or_else(|| by_content(text))
+        .unwrap_or(FileType::Bsl)
+}
@@ -0,0 +1,61 @@
+use std::path::Path;
+use std::process::Command;
+
+use crate::error::Error;
+
+/// default name of tool in git config (`git mergetool --tool=vscode-adapter`)
+pub const DEFAULT_TOOL_NAME: &str = "vscode-adapter";
+/// subcommand for `git mergetool`
+pub const GIT_MERGE_CMD: &str = "git-merge";
+/// subcommand for `git difftool`
+pub const GIT_DIFF_CMD: &str = "git-diff";
+
+/// `value` quoted for `sh` (git runs tool commands with it)
+pub fn sh_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', r"'\''"))
+}
+
+/// git config entries for tool `name` running `exe`, with `merge.tool`/`diff.tool` if `set_default`
+pub fn config_entries(name: &str, exe: &Path, set_default: bool) -> Vec<(String, String)> {
+    let exe = sh_quote(&exe.to_string_lossy());
+    let mut entries = vec![
+        (
+            format!("mergetool.{name}.cmd"),
+            format!(r#"{exe} {GIT_MERGE_CMD} "$LOCAL" "$REMOTE" "$BASE" "$MERGED""#),
+        ),
+        (format!("mergetool.{name}.trustExitCode"), "true".into()),
+        (
+            format!("difftool.{name}.cmd"),
+            format!(r#"{exe} {GIT_DIFF_CMD} "$LOCAL" "$REMOTE""#),
+        ),
+        (format!("difftool.{name}.trustExitCode"), "true".into()),
+    ];
+    if set_default {
+        entries.push(("merge.tool".into(), name.into()));
+        entries.push(("diff.tool".into(), name.into()));
+    }
+    entries
+}
+
+/// write `entries` with `git config` into `file` (default is global config)
+pub fn install(entries: &[(String, String)], file: Option<&Path>) -> Result<(), Error> {
+    for (key, value) in entries {
+        let mut git = Command::new("git");
+        git.arg("config");
+        match file {
+            Some(file) => git.arg("--file").arg(file),
+            None => git.arg("--global"),
+        };
+        let status = git
+            .args([key, value])
+            .status()
+            .map_err(|source| Error::GitLaunch { source })?;
+        if !status.success() {
+            return Err(Error::GitConfig {
+                key: key.clone(),
+                status,
+            });
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,292 @@
+use clap::{builder::PossibleValue, Arg, ArgAction, Command, ValueEnum};
+use std::collections::HashMap;
+use std::env;
+use std::ffi::OsString;
+use std::fmt::Display;
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::OnceLock;
+
+/// env vars with locale, in order of priority
+pub const LOCALE_ENV: [&str; 3] = ["LC_ALL", "LC_MESSAGES", "LANG"];
+/// russian messages by english ones, embedded into binary
+pub const RU_CATALOG: &str = include_str!("../locales/ru.toml");
+
+/// about of `help` subcommand generated by clap
+const HELP_SUBCOMMAND_ABOUT: &str = "Print this message or the help of the given subcommand(s)";
+
+/// language of messages
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum Lang {
+    #[default]
+    En,
+    Ru,
+}
+
+impl Lang {
+    /// language of locale name like `ru_RU.UTF-8`, `None` if not supported
+    pub fn from_locale(locale: &str) -> Option<Self> {
+        let code = locale.split(['_', '-', '.', '@']).next()?.to_lowercase();
+        match code.as_str() {
+            "ru" => Some(Lang::Ru),
+            "en" | "c" | "posix" => Some(Lang::En),
+            _ => None,
+        }
+    }
+
+    /// language from the first set locale env var, english by default
+    pub fn from_env() -> Self {
+        LOCALE_ENV
+            .iter()
+            .filter_map(env::var_os)
+            .find(|value| !value.is_empty())
+            .and_then(|value| Self::from_locale(&value.to_string_lossy()))
+            .unwrap_or_default()
+    }
+
+    /// translations of english messages, `None` for english
+    fn catalog(self) -> Option<&'static HashMap<String, String>> {
+        static RU: OnceLock<HashMap<String, String>> = OnceLock::new();
+        match self {
+            Lang::En => None,
+            Lang::Ru => Some(RU.get_or_init(|| parse_catalog(RU_CATALOG))),
+        }
+    }
+}
+
+/// `message = "translation"` pairs of catalog
+pub fn parse_catalog(text: &str) -> HashMap<String, String> {
+    toml::from_str(text).expect("embedded catalog is valid")
+}
+
+/// 0 is not set, then `Lang` variants
+static CURRENT: AtomicU8 = AtomicU8::new(0);
+
+/// language of messages for the rest of the process
+pub fn set_lang(lang: Lang) {
+    CURRENT.store(lang as u8 + 1, Ordering::Relaxed);
+}
+
+/// language set by `set_lang`, from locale env vars if not set
+pub fn lang() -> Lang {
+    match CURRENT.load(Ordering::Relaxed) {
+        0 => Lang::from_env(),
+        1 => Lang::En,
+        _ => Lang::Ru,
+    }
+}
+
+/// english `message` in `lang`, untranslated if not in catalog
+pub fn translate(lang: Lang, message: &str) -> &str {
+    lang.catalog()
+        .and_then(|catalog| catalog.get(message))
+        .map_or(message, String::as_str)
+}
+
+/// english `message` in current language with `{name}` placeholders replaced by `args`
+pub fn tr(message: &str, args: &[(&str, &dyn Display)]) -> String {
+    tr_in(lang(), message, args)
+}
+
+/// english `message` in `lang` with `{name}` placeholders replaced by `args`
+pub fn tr_in(lang: Lang, message: &str, args: &[(&str, &dyn Display)]) -> String {
+    // values are not expanded again
+    let mut rest = translate(lang, message);
+    let mut text = String::new();
+    while let Some(start) = rest.find('{') {
+        text.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let value = rest.find('}').and_then(|end| {
+            let name = &rest[1..end];
+            let (_, value) = args.iter().find(|(arg, _)| *arg == name)?;
+            Some((end, value))
+        });
+        match value {
+            Some((end, value)) => {
+                text.push_str(&value.to_string());
+                rest = &rest[end + 1..];
+            }
+            None => {
+                text.push('{');
+                rest = &rest[1..];
+            }
+        }
+    }
+    text.push_str(rest);
+    text
+}
+
+/// `--lang` value from raw args, to localize help before args are parsed
+pub fn lang_from_args<I: IntoIterator<Item = OsString>>(args: I) -> Option<Lang> {
+    let mut args = args
+        .into_iter()
+        .map(|arg| arg.to_string_lossy().into_owned());
+    while let Some(arg) = args.next() {
+        let value = match arg.strip_prefix("--lang") {
+            Some("") => args.next()?,
+            Some(value) if value.starts_with('=') => value[1..].to_owned(),
+            _ if arg == "--" => return None,
+            _ => continue,
+        };
+        return Lang::from_str(&value, true).ok();
+    }
+    None
+}
+
+/// `cmd` with help texts and headings in `lang`
+pub fn localize_command(cmd: Command, lang: Lang) -> Command {
+    if lang == Lang::En {
+        return cmd;
+    }
+    let has_version = cmd.get_version().is_some();
+    let mut cmd = localize(cmd, lang, has_version);
+    // `help` subcommand is generated on build
+    cmd.build();
+    if cmd.find_subcommand("help").is_some() {
+        let about = translate(lang, HELP_SUBCOMMAND_ABOUT).to_owned();
+        cmd = cmd.mut_subcommand("help", |sub| sub.about(about));
+    }
+    cmd
+}
+
+/// help of arg in `lang`, default and possible values are shown by it instead of clap
+struct LocalizedArg {
+    id: String,
+    help: Option<String>,
+    /// with possible values described one per line
+    long_help: Option<String>,
+    positional: bool,
+    hide_default: bool,
+    hide_values: bool,
+}
+
+/// `Possible values:` list of long help in `lang`, like clap's one
+fn possible_values(values: &[PossibleValue], lang: Lang) -> String {
+    let width = values
+        .iter()
+        .map(|value| value.get_name().len())
+        .max()
+        .unwrap_or_default();
+    let mut text = format!("{}:", translate(lang, "Possible values"));
+    for value in values {
+        let name = value.get_name();
+        match value.get_help() {
+            Some(help) => {
+                let help = translate(lang, &help.to_string()).to_owned();
+                let name = format!("{name}:");
+                text.push_str(&format!("\n- {name:width$} {help}", width = width + 1));
+            }
+            None => text.push_str(&format!("\n- {name}")),
+        }
+    }
+    text
+}
+
+/// `cmd` and its subcommands in `lang`, `-V` is replaced if version is shown
+fn localize(cmd: Command, lang: Lang, has_version: bool) -> Command {
+    let tr = |text: &str| translate(lang, text).to_owned();
+    let heading = |text: &'static str| translate(lang, text);
+    let about = cmd.get_about().map(|about| tr(&about.to_string()));
+    // custom headings are sorted by name otherwise, so args keep their order
+    let args: Vec<LocalizedArg> = cmd
+        .get_arguments()
+        .map(|arg| {
+            let mut help = arg.get_help().map(|help| tr(&help.to_string()));
+            let defaults: Vec<_> = arg
+                .get_default_values()
+                .iter()
+                .map(|value| value.to_string_lossy())
+                .collect();
+            // clap shows default in english
+            let show_default = !defaults.is_empty() && !arg.is_hide_default_value_set();
+            if let Some(help) = help.as_mut().filter(|_| show_default) {
+                help.push_str(&format!(" [{}: {}]", tr("default"), defaults.join(", ")));
+            }
+            // and possible values too
+            let values: Vec<_> = arg
+                .get_possible_values()
+                .into_iter()
+                .filter(|value| !value.is_hide_set())
+                .collect();
+            let show_values = !values.is_empty() && !arg.is_hide_possible_values_set();
+            let mut long_help = None;
+            if let Some(help) = help.as_mut().filter(|_| show_values) {
+                if values.iter().any(|value| value.get_help().is_some()) {
+                    long_help = Some(format!("{help}\n\n{}", possible_values(&values, lang)));
+                }
+                let names: Vec<_> = values.iter().map(|value| value.get_name()).collect();
+                help.push_str(&format!(
+                    " [{}: {}]",
+                    tr("possible values"),
+                    names.join(", ")
+                ));
+            }
+            LocalizedArg {
+                id: arg.get_id().to_string(),
+                help,
+                long_help,
+                positional: arg.is_positional(),
+                hide_default: show_default,
+                hide_values: show_values,
+            }
+        })
+        .collect();
+    let subcommands: Vec<String> = cmd
+        .get_subcommands()
+        .map(|sub| sub.get_name().to_owned())
+        .collect();
+    let propagate_version = has_version && cmd.is_propagate_version_set();
+
+    let count = args.len();
+    let mut cmd = cmd
+        .help_template(format!(
+            "{{before-help}}{{about-with-newline}}\n{} {{usage}}\n\n{{all-args}}{{after-help}}",
+            tr("Usage:")
+        ))
+        .subcommand_help_heading(heading("Commands"));
+    if let Some(about) = about {
+        cmd = cmd.about(about);
+    }
+    for (order, localized) in args.into_iter().enumerate() {
+        let positional = localized.positional;
+        cmd = cmd.mut_arg(localized.id, |arg| {
+            let arg = arg
+                .display_order(order)
+                .help_heading(heading(if positional { "Arguments" } else { "Options" }))
+                .hide_default_value(localized.hide_default)
+                .hide_possible_values(localized.hide_values);
+            let arg = match localized.long_help {
+                Some(long_help) => arg.long_help(long_help),
+                None => arg,
+            };
+            match localized.help {
+                Some(help) => arg.help(help),
+                None => arg,
+            }
+        });
+    }
+    // after args: headings are listed in order of their first arg
+    cmd = cmd.disable_help_flag(true).arg(
+        Arg::new("help")
+            .short('h')
+            .long("help")
+            .action(ArgAction::Help)
+            .help(tr("Print help"))
+            .help_heading(heading("Options"))
+            .display_order(count),
+    );
+    if has_version {
+        cmd = cmd.disable_version_flag(true).arg(
+            Arg::new("version")
+                .short('V')
+                .long("version")
+                .action(ArgAction::Version)
+                .help(tr("Print version"))
+                .help_heading(heading("Options"))
+                .display_order(count + 1),
+        );
+    }
+    for name in subcommands {
+        cmd = cmd.mut_subcommand(name, |sub| localize(sub, lang, propagate_version));
+    }
+    cmd
+}
@@ -0,0 +1,125 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::ffi::OsString;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::error::Error;
+
+/// progress of file in merge of trees
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "kebab-case")]
+pub enum FileState {
+    /// not merged yet
+    Pending,
+    /// taken from one side or merged by lines without editor
+    AutoMerged,
+    /// merged in editor
+    Merged,
+    /// left with conflicts (or not changed in editor), merged again from result on resume
+    Skipped,
+}
+
+impl FileState {
+    pub const ALL: [FileState; 4] = [
+        FileState::Pending,
+        FileState::AutoMerged,
+        FileState::Merged,
+        FileState::Skipped,
+    ];
+
+    /// name in journal and `status`
+    pub fn name(&self) -> &'static str {
+        match self {
+            FileState::Pending => "pending",
+            FileState::AutoMerged => "auto-merged",
+            FileState::Merged => "merged",
+            FileState::Skipped => "skipped",
+        }
+    }
+}
+
+/// state of each changed file of `merge-dir`, saved after each file to resume merge
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Journal {
+    /// base, second, old vendor and result directories
+    pub dirs: [PathBuf; 4],
+    /// state by path relative to tree roots
+    pub files: BTreeMap<PathBuf, FileState>,
+    #[serde(skip)]
+    path: PathBuf,
+}
+
+impl Journal {
+    /// empty journal of merge of `dirs` to be saved in `path`
+    pub fn new(path: &Path, dirs: [PathBuf; 4]) -> Self {
+        Self {
+            dirs,
+            files: BTreeMap::new(),
+            path: path.to_path_buf(),
+        }
+    }
+
+    /// saved journal, `None` if there is no file
+    pub fn load(path: &Path) -> Result<Option<Self>, Error> {
+        let error = |source| Error::Journal {
+            path: path.to_path_buf(),
+            source,
+        };
+        let text = match fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(error(err)),
+        };
+        let mut journal: Self = serde_json::from_str(&text).map_err(|err| error(err.into()))?;
+        journal.path = path.to_path_buf();
+        Ok(Some(journal))
+    }
+
+    /// saved journal of merge of `dirs` or a new one
+    pub fn open(path: &Path, dirs: &[PathBuf; 4]) -> Result<Self, Error> {
+        match Self::load(path)? {
+            Some(journal) if journal.dirs == *dirs => Ok(journal),
+            Some(_) => Err(Error::JournalMismatch {
+                path: path.to_path_buf(),
+            }),
+            None => Ok(Self::new(path, dirs.clone())),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// state of file at relative `path`, `None` if it is not in journal
+    pub fn state(&self, path: &Path) -> Option<FileState> {
+        self.files.get(path).copied()
+    }
+
+    pub fn set(&mut self, path: &Path, state: FileState) {
+        self.files.insert(path.to_path_buf(), state);
+    }
+
+    /// number of files in each state
+    pub fn counts(&self) -> BTreeMap<FileState, usize> {
+        let mut counts = BTreeMap::new();
+        for state in self.files.values() {
+            *counts.entry(*state).or_default() += 1;
+        }
+        counts
+    }
+
+    /// write to file (through temporary file, journal is never left half-written)
+    pub fn save(&self) -> Result<(), Error> {
+        let error = |source| Error::Journal {
+            path: self.path.clone(),
+            source,
+        };
+        let text = serde_json::to_string_pretty(self).map_err(|err| error(err.into()))?;
+        let mut tmp = OsString::from(&self.path);
+        tmp.push(".tmp");
+        fs::write(&tmp, text + "\n").map_err(error)?;
+        fs::rename(&tmp, &self.path).map_err(error)
+    }
+}
@@ -0,0 +1,1606 @@
+//! VSCode (or other editor) as diff/merge tool for 1C:Enterprise designer
+//!
+//! `Program` stages files from the platform, launches editor with `WrappedCommand`
+//! and writes merge result back, the CLI is built on it.
+
+use clap::{Parser, Subcommand};
+pub use config::{Config, MergeSeed};
+use conflicts::find_markers;
+pub use discovery::{EditorNotFound, EditorSearch};
+use editor::Backend;
+use encoding::TextEncoding;
+pub use error::Error;
+use exit::{EXIT_CONFLICTS, EXIT_DIFFERENT, EXIT_EDITOR_FAILED, EXIT_SUCCESS, EXIT_UNCHANGED};
+use filetype::FileType;
+use i18n::{tr, translate, Lang};
+use journal::{FileState, Journal};
+use log::info;
+use merge3::{Labels, Merged};
+use profile::{Profile, Role, DIFF_ROLES, MERGE_ROLES};
+use report::DiffFormat;
+use staging::{check_interrupted, interrupted};
+pub use staging::{Transaction, Workspace};
+use tree::{Change, MergeEntry, Resolution, Side};
+pub use utils::{
+    file_hash, read_file, read_text, set_ext_to_all, write_back, EditorCommand, WrappedCommand,
+};
+
+use std::collections::HashMap;
+use std::ffi::{OsStr, OsString};
+use std::fmt::Display;
+use std::path::{Path, PathBuf};
+use std::process::ExitStatus;
+use std::{env, fs, io, iter};
+
+mod bsl;
+pub mod config;
+mod conflicts;
+pub mod discovery;
+pub mod editor;
+pub mod encoding;
+pub mod error;
+pub mod exit;
+pub mod filetype;
+pub mod git;
+pub mod i18n;
+pub mod journal;
+pub mod logging;
+mod merge3;
+mod paths;
+pub mod profile;
+pub mod report;
+pub mod staging;
+#[cfg(test)]
+mod test;
+pub mod tree;
+pub mod utils;
+
+/// filename extension for syntax highlights
+pub const EXTENSION_BSL: &str = "bsl";
+/// how many times editor is reopened by `--reopen-on-conflicts`
+pub const MAX_REOPEN: usize = 3;
+
+/// command line args
+#[derive(Parser, Debug)]
+#[clap(author, version, about, long_about = None)]
+#[clap(propagate_version = true)]
+pub struct Cli {
+    /// diff or merge
+    #[clap(subcommand)]
+    pub command: Action,
+    /// remove source files moved by `--rename-files` (staged copies are always removed)
+    #[clap(short, long, action, overrides_with = "no_remove_files")]
+    pub remove_files: bool,
+    /// keep source files (overrides config)
+    #[clap(long, action, overrides_with = "remove_files")]
+    pub no_remove_files: bool,
+    /// move source files to staging directory instead of copy (moved back after editor is closed)
+    #[clap(short = 'n', long, action, overrides_with = "no_rename_files")]
+    pub rename_files: bool,
+    /// copy source files (overrides config)
+    #[clap(long, action, overrides_with = "rename_files")]
+    pub no_rename_files: bool,
+    /// diff/merge editor [default: vscode]
+    #[clap(short, long, value_enum)]
+    pub backend: Option<Backend>,
+    /// path to editor executable (default is searched in PATH and install locations)
+    #[clap(short, long, value_parser)]
+    pub editor: Option<PathBuf>,
+    /// encoding of merge result [default: same as source]
+    #[clap(long, value_enum)]
+    pub encoding: Option<TextEncoding>,
+    /// directory for staging directories [default: system temp dir]
+    #[clap(long, value_parser)]
+    pub staging_dir: Option<PathBuf>,
+    /// reopen editor if merge result has conflict markers (up to 3 times)
+    #[clap(long, action, overrides_with = "no_reopen_on_conflicts")]
+    pub reopen_on_conflicts: bool,
+    /// don't reopen editor (overrides config)
+    #[clap(long, action, overrides_with = "reopen_on_conflicts")]
+    pub no_reopen_on_conflicts: bool,
+    /// report merge result not changed in editor with `--unchanged-exit-code`
+    #[clap(long, action, overrides_with = "no_detect_unchanged")]
+    pub detect_unchanged: bool,
+    /// don't report unchanged merge result (overrides config)
+    #[clap(long, action, overrides_with = "detect_unchanged")]
+    pub no_detect_unchanged: bool,
+    /// exit code for unchanged merge result [default: 4]
+    #[clap(long, value_parser)]
+    pub unchanged_exit_code: Option<i32>,
+    /// merge non-conflicting changes without editor, editor is opened with conflicts only
+    #[clap(short, long, action, overrides_with = "no_auto_merge")]
+    pub auto_merge: bool,
+    /// always open editor (overrides config)
+    #[clap(long, action, overrides_with = "auto_merge")]
+    pub no_auto_merge: bool,
+    /// match BSL procedures and functions by name, merge lines inside them only (implies `--auto-merge`)
+    #[clap(long, action, overrides_with = "no_structural")]
+    pub structural: bool,
+    /// merge lines of whole modules (overrides config)
+    #[clap(long, action, overrides_with = "structural")]
+    pub no_structural: bool,
+    /// profile from config: order of args and editor args template
+    #[clap(short, long, value_parser)]
+    pub profile: Option<String>,
+    /// print planned file operations and editor command line, change nothing
+    #[clap(long, action)]
+    pub dry_run: bool,
+    /// log more details: `-v` for debug, `-vv` for trace [default: info]
+    #[clap(short, long, action = clap::ArgAction::Count)]
+    pub verbose: u8,
+    /// config file (overrides per-user and per-project configs)
+    #[clap(long, value_parser)]
+    pub config: Option<PathBuf>,
+    /// language of messages and help [default: from LANG]
+    #[clap(long, value_enum)]
+    pub lang: Option<Lang>,
+}
+
+/// diff or merge
+#[derive(Subcommand, Debug)]
+pub enum Action {
+    /// diff 2 files
+    Diff {
+        /// first file / %baseCfg
+        #[clap(value_parser)]
+        base_cfg: PathBuf,
+        /// second file / %secondCfg
+        #[clap(value_parser)]
+        second_cfg: PathBuf,
+        /// title of first file / %baseCfgTitle
+        #[clap(long, value_parser)]
+        base_title: Option<String>,
+        /// title of second file / %secondCfgTitle
+        #[clap(long, value_parser)]
+        second_title: Option<String>,
+        /// print diff to stdout instead of opening editor (exit code is 1 if files differ)
+        #[clap(short, long, value_enum)]
+        output: Option<DiffFormat>,
+    },
+    /// merge 3 files into 1
+    Merge {
+        /// first file (from base config) / %baseCfg
+        #[clap(value_parser)]
+        base_cfg: PathBuf,
+        /// second file (from new config) / %secondCfg
+        #[clap(value_parser)]
+        second_cfg: PathBuf,
+        /// third file (from old vendor config) / %oldVendorCfg
+        #[clap(value_parser)]
+        old_vendor_cfg: PathBuf,
+        /// merge result file / %merged
+        #[clap(value_parser)]
+        merged: PathBuf,
+        /// start merge result from second file (default is first file)
+        #[clap(short = 's', long, action)]
+        from_second: bool,
+        /// title of first file / %baseCfgTitle
+        #[clap(long, value_parser)]
+        base_title: Option<String>,
+        /// title of second file / %secondCfgTitle
+        #[clap(long, value_parser)]
+        second_title: Option<String>,
+        /// title of third file / %oldVendorCfgTitle
+        #[clap(long, value_parser)]
+        old_vendor_title: Option<String>,
+    },
+    /// diff 2 directory trees (configuration dumps) file by file
+    DiffDir {
+        /// first directory
+        #[clap(value_parser)]
+        base_dir: PathBuf,
+        /// second directory
+        #[clap(value_parser)]
+        second_dir: PathBuf,
+        /// only list added, removed and changed files (exit code is 1 if trees differ)
+        #[clap(short, long, action)]
+        list: bool,
+    },
+    /// merge 3 directory trees (configuration dumps) into result directory file by file
+    MergeDir {
+        /// directory of base config
+        #[clap(value_parser)]
+        base_dir: PathBuf,
+        /// directory of new vendor config
+        #[clap(value_parser)]
+        second_dir: PathBuf,
+        /// directory of old vendor config
+        #[clap(value_parser)]
+        old_vendor_dir: PathBuf,
+        /// result directory (may be the base one)
+        #[clap(value_parser)]
+        merged_dir: PathBuf,
+        /// start merge results from files of second directory (default is first directory)
+        #[clap(short = 's', long, action)]
+        from_second: bool,
+        /// open all files left to merge in one editor window (with conflict markers with `--auto-merge`)
+        #[clap(short, long, action)]
+        workspace: bool,
+        /// journal file with state of each file, merge is resumed from it
+        #[clap(short, long, value_parser)]
+        journal: Option<PathBuf>,
+    },
+    /// print progress of `merge-dir --journal`
+    Status {
+        /// journal file
+        #[clap(value_parser)]
+        journal: PathBuf,
+    },
+    /// git mergetool: merge into $MERGED (its content is the initial result)
+    GitMerge {
+        /// $LOCAL
+        #[clap(value_parser)]
+        local: PathBuf,
+        /// $REMOTE
+        #[clap(value_parser)]
+        remote: PathBuf,
+        /// $BASE
+        #[clap(value_parser)]
+        base: PathBuf,
+        /// $MERGED
+        #[clap(value_parser)]
+        merged: PathBuf,
+    },
+    /// git difftool: diff $LOCAL and $REMOTE
+    GitDiff {
+        /// $LOCAL
+        #[clap(value_parser)]
+        local: PathBuf,
+        /// $REMOTE
+        #[clap(value_parser)]
+        remote: PathBuf,
+    },
+    /// add this adapter as mergetool and difftool to git config
+    InstallGit {
+        /// tool name
+        #[clap(long, value_parser, default_value = git::DEFAULT_TOOL_NAME)]
+        name: String,
+        /// git config file [default: global config]
+        #[clap(long, value_parser)]
+        file: Option<PathBuf>,
+        /// also set `merge.tool` and `diff.tool`
+        #[clap(long, action)]
+        set_default: bool,
+    },
+}
+
+impl Action {
+    /// reorder positional args as declared in `profile`
+    fn apply_profile(&mut self, profile: &Profile) -> Result<(), Error> {
+        match self {
+            Action::Diff {
+                base_cfg,
+                second_cfg,
+                ..
+            } => {
+                if let Some(order) = profile.diff.as_deref() {
+                    let args = [base_cfg.clone(), second_cfg.clone()];
+                    [*base_cfg, *second_cfg] = profile::arrange(args, order, DIFF_ROLES)?;
+                }
+            }
+            Action::Merge {
+                base_cfg,
+                second_cfg,
+                old_vendor_cfg,
+                merged,
+                ..
+            } => {
+                if let Some(order) = profile.merge.as_deref() {
+                    let args = [
+                        base_cfg.clone(),
+                        second_cfg.clone(),
+                        old_vendor_cfg.clone(),
+                        merged.clone(),
+                    ];
+                    [*base_cfg, *second_cfg, *old_vendor_cfg, *merged] =
+                        profile::arrange(args, order, MERGE_ROLES)?;
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// editor is launched (not headless diff or git setup)
+    pub fn needs_editor(&self) -> bool {
+        !matches!(
+            self,
+            Action::Diff {
+                output: Some(_),
+                ..
+            } | Action::DiffDir { list: true, .. }
+                | Action::Status { .. }
+                | Action::InstallGit { .. }
+        )
+    }
+
+    /// normalize all paths from the platform
+    fn normalize_paths(&mut self, cwd: &Path) {
+        let paths = match self {
+            Action::Diff {
+                base_cfg,
+                second_cfg,
+                ..
+            } => vec![base_cfg, second_cfg],
+            Action::Merge {
+                base_cfg,
+                second_cfg,
+                old_vendor_cfg,
+                merged,
+                ..
+            } => vec![base_cfg, second_cfg, old_vendor_cfg, merged],
+            Action::DiffDir {
+                base_dir,
+                second_dir,
+                ..
+            } => vec![base_dir, second_dir],
+            Action::MergeDir {
+                base_dir,
+                second_dir,
+                old_vendor_dir,
+                merged_dir,
+                journal,
+                ..
+            } => {
+                let mut paths = vec![base_dir, second_dir, old_vendor_dir, merged_dir];
+                paths.extend(journal);
+                paths
+            }
+            Action::Status { journal } => vec![journal],
+            Action::GitMerge {
+                local,
+                remote,
+                base,
+                merged,
+            } => vec![local, remote, base, merged],
+            Action::GitDiff { local, remote } => vec![local, remote],
+            Action::InstallGit { .. } => vec![],
+        };
+        for path in paths {
+            *path = paths::normalize(path, cwd);
+        }
+    }
+}
+
+/// files of merge of 3 files into 1
+struct MergeFiles {
+    base_cfg: PathBuf,
+    second_cfg: PathBuf,
+    /// `None` for file added on both sides: empty ancestor is staged
+    old_vendor_cfg: Option<PathBuf>,
+    merged: PathBuf,
+    /// titles of base, second and old vendor files
+    titles: [Option<String>; 3],
+}
+
+/// diff/merge of files from the platform with editor `vscmd`, see `Program::builder`
+pub struct Program<C: WrappedCommand> {
+    remove_files: bool,
+    rename_files: bool,
+    merge_seed: MergeSeed,
+    backend: Backend,
+    editor_args: Vec<String>,
+    /// extension of modules
+    extension: String,
+    /// file type by marker in file name
+    file_types: Vec<(String, FileType)>,
+    output_encoding: Option<TextEncoding>,
+    staging_dir: Option<PathBuf>,
+    reopen_on_conflicts: bool,
+    auto_merge: bool,
+    structural: bool,
+    /// exit code for unchanged merge result, `None` if not detected
+    unchanged_exit_code: Option<i32>,
+    /// name of profile from `profiles`
+    profile: Option<String>,
+    profiles: HashMap<String, Profile>,
+    /// print operations instead of doing them
+    dry_run: bool,
+    vscmd: C,
+    action: Option<Action>,
+}
+
+impl<C: WrappedCommand> Program<C> {
+    /// options with defaults (same as without CLI flags and config), `action` is done by `run`
+    pub fn builder(action: Action, vscmd: C) -> ProgramBuilder<C> {
+        ProgramBuilder {
+            program: Self {
+                remove_files: false,
+                rename_files: false,
+                merge_seed: MergeSeed::default(),
+                backend: Backend::default(),
+                editor_args: vec![],
+                extension: EXTENSION_BSL.to_string(),
+                file_types: vec![],
+                output_encoding: None,
+                staging_dir: None,
+                reopen_on_conflicts: false,
+                auto_merge: false,
+                structural: false,
+                unchanged_exit_code: None,
+                profile: None,
+                profiles: HashMap::new(),
+                dry_run: false,
+                vscmd,
+                action: Some(action),
+            },
+        }
+    }
+
+    /// CLI flags (and their `--no-` counterparts) take precedence over `config`
+    pub fn new(cli: Cli, config: Config, vscmd: C) -> Self {
+        let detect_unchanged = flag(cli.detect_unchanged, cli.no_detect_unchanged)
+            .or(config.detect_unchanged)
+            .unwrap_or_default();
+        Self::builder(cli.command, vscmd)
+            .remove_files(
+                flag(cli.remove_files, cli.no_remove_files)
+                    .or(config.remove_files)
+                    .unwrap_or_default(),
+            )
+            .rename_files(
+                flag(cli.rename_files, cli.no_rename_files)
+                    .or(config.rename_files)
+                    .unwrap_or_default(),
+            )
+            .merge_seed(config.merge_seed.unwrap_or_default())
+            .backend(cli.backend.or(config.backend).unwrap_or_default())
+            .editor_args(config.editor_args.unwrap_or_default())
+            .extension(
+                config
+                    .extension
+                    .unwrap_or_else(|| EXTENSION_BSL.to_string()),
+            )
+            .file_types(config.file_types.unwrap_or_default().into_iter().collect())
+            .output_encoding(cli.encoding.or(config.output_encoding))
+            .staging_dir(cli.staging_dir.or(config.staging_dir))
+            .reopen_on_conflicts(
+                flag(cli.reopen_on_conflicts, cli.no_reopen_on_conflicts)
+                    .or(config.reopen_on_conflicts)
+                    .unwrap_or_default(),
+            )
+            .auto_merge(
+                flag(cli.auto_merge, cli.no_auto_merge)
+                    .or(config.auto_merge)
+                    .unwrap_or_default(),
+            )
+            .structural(
+                flag(cli.structural, cli.no_structural)
+                    .or(config.structural)
+                    .unwrap_or_default(),
+            )
+            .unchanged_exit_code(detect_unchanged.then(|| {
+                cli.unchanged_exit_code
+                    .or(config.unchanged_exit_code)
+                    .unwrap_or(EXIT_UNCHANGED)
+            }))
+            .profile(cli.profile.or(config.profile))
+            .profiles(config.profiles.unwrap_or_default())
+            .dry_run(cli.dry_run)
+            .build()
+    }
+
+    /// diff 2 files
+    fn command_diff(
+        &mut self,
+        mut base_cfg: PathBuf,
+        mut second_cfg: PathBuf,
+        titles: [Option<String>; 2],
+    ) -> Result<i32, Error> {
+        let file_type = self.file_type(&base_cfg)?;
+        let extension = file_type.extension(&self.extension).to_string();
+        // dropped in reverse order: files are restored before workspace is removed
+        let workspace = self.workspace(&extension, file_type)?;
+        let mut tx = self.transaction(&workspace);
+        let mut files = [&mut base_cfg, &mut second_cfg];
+        set_ext_to_all(
+            &mut files,
+            &titles,
+            &workspace,
+            &mut tx,
+            &extension,
+            self.rename_files,
+        )?;
+
+        let workspace_arg = workspace.path().as_os_str();
+        let roles = [Role::Base, Role::Second];
+        let values = [files[0].as_os_str(), files[1].as_os_str()];
+        let args: Vec<OsString> = match self.profile()?.diff_args.as_deref() {
+            Some(template) => profile::expand(template, workspace_arg, &zip(roles, values)),
+            None => to_os_strings(self.backend.diff_args(workspace_arg, values[0], values[1])),
+        };
+
+        if self.dry_run {
+            self.print_launch(&args);
+            self.finish(tx, workspace)?;
+            return Ok(0);
+        }
+        self.vscmd.args(&self.editor_args).args(args);
+        let status = self.launch_editor()?;
+
+        self.finish(tx, workspace)?;
+        check_interrupted()?;
+
+        Ok(exit::editor_code(status))
+    }
+
+    /// print diff of 2 files without editor, 1 if they differ
+    fn print_diff(
+        &mut self,
+        base_cfg: PathBuf,
+        second_cfg: PathBuf,
+        titles: [Option<String>; 2],
+        format: DiffFormat,
+    ) -> Result<i32, Error> {
+        let [base_title, second_title] = titles;
+        let differ = report::write_diff(
+            &mut io::stdout().lock(),
+            format,
+            &base_title.unwrap_or_else(|| base_cfg.display().to_string()),
+            &second_title.unwrap_or_else(|| second_cfg.display().to_string()),
+            &read_text(&base_cfg)?.0,
+            &read_text(&second_cfg)?.0,
+        )
+        .map_err(|source| Error::Output { source })?;
+        Ok(if differ { EXIT_DIFFERENT } else { EXIT_SUCCESS })
+    }
+
+    /// merge 3 files into 1: by lines first with `auto_merge`, then in editor
+    fn command_merge(&mut self, files: MergeFiles, seed: MergeSeed) -> Result<i32, Error> {
+        let file_type = self.file_type(&files.base_cfg)?;
+        let mut seed_text = None;
+        if self.auto_merge || self.structural {
+            match self.auto_merge_files(&files, file_type, seed)? {
+                Some(text) => seed_text = Some(text),
+                None => return Ok(EXIT_SUCCESS),
+            }
+        }
+        self.merge_in_editor(files, file_type, seed, seed_text)
+    }
+
+    /// merge `files` by lines without editor: `None` if result is written, text with conflict
+    /// markers otherwise
+    fn auto_merge_files(
+        &self,
+        files: &MergeFiles,
+        file_type: FileType,
+        seed: MergeSeed,
+    ) -> Result<Option<String>, Error> {
+        let MergeFiles {
+            base_cfg,
+            second_cfg,
+            old_vendor_cfg,
+            merged,
+            titles,
+        } = files;
+        let sources: Vec<&PathBuf> = [base_cfg, second_cfg]
+            .into_iter()
+            .chain(old_vendor_cfg)
+            .collect();
+        let mut texts = vec![];
+        let mut encodings = vec![];
+        for source in &sources {
+            let (text, encoding) = read_text(source)?;
+            texts.push(text);
+            encodings.push(encoding);
+        }
+        // empty ancestor
+        texts.resize(3, String::new());
+        let label = |i: usize| match (&titles[i], sources.get(i)) {
+            (Some(title), _) => title.clone(),
+            (None, Some(source)) => source
+                .file_name()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .into(),
+            (None, None) => String::new(),
+        };
+        let labels = [label(0), label(1), label(2)];
+        let labels = Labels {
+            ours: &labels[0],
+            theirs: &labels[1],
+            ancestor: &labels[2],
+        };
+        let texts = [&texts[2], &texts[0], &texts[1]].map(String::as_str);
+        match self.merge_texts(file_type, texts, labels) {
+            Merged::Clean(text) => {
+                let seed_encoding = match merged_seed(merged, seed)? {
+                    Some(bytes) => encoding::detect(&bytes),
+                    None => encodings[seed_index(seed)],
+                };
+                let output_encoding = self.output_encoding.unwrap_or(seed_encoding);
+                let remove_sources = self.rename_files && self.remove_files;
+                self.write_merged(merged, &text, output_encoding)?;
+                for path in sources.iter().filter(|_| remove_sources) {
+                    self.remove_file(path)?;
+                }
+                if !self.dry_run {
+                    eprintln!(
+                        "{}",
+                        tr(
+                            "merged without conflicts into {path}",
+                            &[("path", &merged.display())]
+                        )
+                    );
+                }
+                Ok(None)
+            }
+            Merged::Conflicts { text, count } => {
+                eprintln!(
+                    "{}",
+                    tr(
+                        "{count} conflicts left after automatic merge",
+                        &[("count", &count)]
+                    )
+                );
+                Ok(Some(text))
+            }
+        }
+    }
+
+    /// merge `files` of `file_type` in editor, result is seeded with `seed_text` if any (conflicts
+    /// left by automatic merge)
+    fn merge_in_editor(
+        &mut self,
+        files: MergeFiles,
+        file_type: FileType,
+        seed: MergeSeed,
+        seed_text: Option<String>,
+    ) -> Result<i32, Error> {
+        let MergeFiles {
+            mut base_cfg,
+            mut second_cfg,
+            mut old_vendor_cfg,
+            merged,
+            titles,
+        } = files;
+        let extension = file_type.extension(&self.extension).to_string();
+        // current content of result (git mergetool), if it is the seed
+        let merged_seed = merged_seed(&merged, seed)?;
+        let seed_index = seed_index(seed);
+
+        // dropped in reverse order: files are restored before workspace is removed
+        let workspace = self.workspace(&extension, file_type)?;
+        let mut tx = self.transaction(&workspace);
+        let mut empty_ancestor;
+        let mut files: Vec<&mut PathBuf> = [&mut base_cfg, &mut second_cfg]
+            .into_iter()
+            .chain(&mut old_vendor_cfg)
+            .collect();
+        let encodings = set_ext_to_all(
+            &mut files,
+            &titles,
+            &workspace,
+            &mut tx,
+            &extension,
+            self.rename_files,
+        )?;
+        // empty ancestor of file added on both sides
+        if files.len() < 3 {
+            let stem = titles[2].as_deref().and_then(paths::sanitize_file_name);
+            let stem = OsString::from(stem.as_deref().unwrap_or("old_vendor_cfg"));
+            empty_ancestor = workspace.file_path(&stem, &extension);
+            tx.write(&empty_ancestor, b"")
+                .map_err(|source| Error::Workspace {
+                    dir: workspace.path().to_path_buf(),
+                    source,
+                })?;
+            files.push(&mut empty_ancestor);
+        }
+
+        let merged_stem = merged.file_stem().unwrap_or_default();
+        let merged_staged = workspace.file_path(merged_stem, &extension);
+        let mut seed_encoding = encodings[seed_index];
+        let seeded = match (seed_text, merged_seed) {
+            (Some(text), _) => tx.write(
+                &merged_staged,
+                &encoding::encode(&text, TextEncoding::Utf8Bom),
+            ),
+            (None, Some(bytes)) => {
+                let (encoding, converted) = encoding::to_editor(&bytes);
+                seed_encoding = encoding;
+                tx.write(&merged_staged, &converted.unwrap_or(bytes))
+            }
+            (None, None) => tx.copy(files[seed_index], &merged_staged),
+        };
+        seeded.map_err(|source| Error::Seed {
+            path: merged_staged.clone(),
+            source,
+        })?;
+        let output_encoding = self.output_encoding.unwrap_or(seed_encoding);
+
+        let workspace_arg = workspace.path().as_os_str();
+        let values = [
+            files[0].as_os_str(),
+            files[1].as_os_str(),
+            files[2].as_os_str(),
+            merged_staged.as_os_str(),
+        ];
+        let args: Vec<OsString> = match self.profile()?.merge_args.as_deref() {
+            Some(template) => profile::expand(template, workspace_arg, &zip(MERGE_ROLES, values)),
+            None => {
+                let [base, second, old_vendor, merged] = values;
+                to_os_strings(self.backend.merge_args(
+                    workspace_arg,
+                    base,
+                    second,
+                    old_vendor,
+                    merged,
+                ))
+            }
+        };
+
+        if self.dry_run {
+            self.print_launch(&args);
+            print_write_back(&merged_staged, &merged, output_encoding);
+            self.finish(tx, workspace)?;
+            return Ok(0);
+        }
+        let seed_hash = file_hash(&merged_staged)?;
+        self.vscmd.args(&self.editor_args).args(args);
+        let mut status = self.launch_editor()?;
+        let mut reopened = 0;
+        let markers = loop {
+            if interrupted() || !status.success() {
+                break vec![];
+            }
+            let markers = find_markers(&read_text(&merged_staged)?.0);
+            if markers.is_empty() || !self.reopen_on_conflicts || reopened == MAX_REOPEN {
+                break markers;
+            }
+            eprintln!("{}", tr("conflict markers left, reopening editor", &[]));
+            reopened += 1;
+            status = self.launch_editor()?;
+        };
+
+        let unchanged = match self.unchanged_exit_code {
+            Some(_) if status.success() && !interrupted() => {
+                file_hash(&merged_staged)? == seed_hash
+            }
+            _ => false,
+        };
+
+        if !interrupted() {
+            write_back(&merged_staged, &merged, output_encoding)?;
+        }
+        self.finish(tx, workspace)?;
+        check_interrupted()?;
+
+        if !markers.is_empty() {
+            report_markers(&merged, &markers);
+            return Ok(EXIT_CONFLICTS);
+        }
+        if let Some(code) = self.unchanged_exit_code.filter(|_| unchanged) {
+            eprintln!(
+                "{}",
+                tr(
+                    "merge result {path} is not changed",
+                    &[("path", &merged.display())]
+                )
+            );
+            return Ok(code);
+        }
+        Ok(exit::editor_code(status))
+    }
+
+    /// diff 2 directory trees: list added, removed and changed files, diff changed ones in editor
+    /// one by one
+    fn command_diff_dir(
+        &mut self,
+        base_dir: PathBuf,
+        second_dir: PathBuf,
+        list: bool,
+    ) -> Result<i32, Error> {
+        let entries = tree::diff(&base_dir, &second_dir)?;
+        let mut differ = false;
+        for entry in entries
+            .iter()
+            .filter(|entry| entry.change != Change::Identical)
+        {
+            println!("{} {}", entry.change.mark(), entry.path.display());
+            differ = true;
+        }
+        if list {
+            return Ok(if differ { EXIT_DIFFERENT } else { EXIT_SUCCESS });
+        }
+        let dirs = [base_dir, second_dir];
+        for entry in entries
+            .iter()
+            .filter(|entry| entry.change == Change::Changed)
+        {
+            let [base_cfg, second_cfg] = dirs.each_ref().map(|dir| dir.join(&entry.path));
+            let titles = dirs
+                .each_ref()
+                .map(|dir| Some(tree::title(dir, &entry.path)));
+            self.vscmd.clear_args();
+            let code = self.command_diff(base_cfg, second_cfg, titles)?;
+            if code != EXIT_SUCCESS {
+                return Ok(code);
+            }
+        }
+        Ok(EXIT_SUCCESS)
+    }
+
+    /// merge directory trees `dirs` (base, second, old vendor) into the last one: files changed
+    /// on one side are taken as is, the rest are merged in editor one by one or in one `workspace`,
+    /// files done according to `journal` are not merged again
+    fn command_merge_dir(
+        &mut self,
+        dirs: [PathBuf; 4],
+        seed: MergeSeed,
+        workspace: bool,
+        journal: Option<PathBuf>,
+    ) -> Result<i32, Error> {
+        let [base_dir, second_dir, old_vendor_dir, merged_dir] = &dirs;
+        let entries = tree::merge(base_dir, second_dir, old_vendor_dir)?;
+        let mut journal = match journal {
+            Some(path) => Some(Journal::open(&path, &dirs)?),
+            None => None,
+        };
+        if let Some(journal) = &mut journal {
+            for entry in entries.iter().filter(|entry| !entry.is_identical()) {
+                if journal.state(&entry.path).is_none() {
+                    journal.set(&entry.path, FileState::Pending);
+                }
+            }
+            if !self.dry_run {
+                journal.save()?;
+            }
+        }
+
+        let mut left = vec![];
+        let mut conflicts = vec![];
+        let mut resolved = 0;
+        let mut done = 0;
+        for entry in &entries {
+            let state = journal
+                .as_ref()
+                .and_then(|journal| journal.state(&entry.path));
+            if !entry.is_identical() {
+                let [ours, theirs] = [entry.ours.mark(), entry.theirs.mark()];
+                println!("{ours}{theirs} {}", entry.path.display());
+            }
+            // skipped files are merged again from their results
+            if matches!(state, Some(FileState::AutoMerged | FileState::Merged)) {
+                done += 1;
+                continue;
+            }
+            match entry.resolution {
+                Resolution::Take(side) => {
+                    let source = side.map(|side| match side {
+                        Side::Base => base_dir.join(&entry.path),
+                        Side::Second => second_dir.join(&entry.path),
+                    });
+                    self.take(source.as_deref(), &merged_dir.join(&entry.path))?;
+                    if !entry.is_identical() {
+                        self.record(&mut journal, &entry.path, FileState::AutoMerged)?;
+                        resolved += 1;
+                    }
+                }
+                Resolution::Merge => left.push(entry),
+                Resolution::Conflict => {
+                    self.record(&mut journal, &entry.path, FileState::Skipped)?;
+                    conflicts.push(entry);
+                }
+            }
+        }
+        if done > 0 {
+            eprintln!(
+                "{}",
+                tr(
+                    "{done} files are done in previous sessions",
+                    &[("done", &done)]
+                )
+            );
+        }
+        let args: [(&str, &dyn Display); 2] = [("resolved", &resolved), ("left", &left.len())];
+        eprintln!(
+            "{}",
+            tr(
+                "{resolved} files resolved automatically, {left} left to merge",
+                &args
+            )
+        );
+
+        let code = if left.is_empty() {
+            EXIT_SUCCESS
+        } else if workspace && self.backend.opens_workspace() {
+            self.merge_in_workspace(&dirs, &left, seed, &mut journal)?
+        } else {
+            if workspace {
+                let backend = format!("{:?}", self.backend);
+                eprintln!(
+                    "{}",
+                    tr(
+                        "{backend} can't open workspace, merging files one by one",
+                        &[("backend", &backend)]
+                    )
+                );
+            }
+            self.merge_one_by_one(&dirs, &left, seed, &mut journal)?
+        };
+        for entry in &conflicts {
+            eprintln!(
+                "{}",
+                tr(
+                    "{path} is removed on one side and changed on the other",
+                    &[("path", &entry.path.display())]
+                )
+            );
+        }
+        // including files skipped in previous sessions
+        let skipped = journal
+            .iter()
+            .flat_map(|journal| journal.files.values())
+            .any(|state| *state == FileState::Skipped);
+        if code == EXIT_SUCCESS && (skipped || !conflicts.is_empty()) {
+            return Ok(EXIT_CONFLICTS);
+        }
+        Ok(code)
+    }
+
+    /// merge `entries` of trees `dirs` in editor one by one, stops if editor fails
+    fn merge_one_by_one(
+        &mut self,
+        dirs: &[PathBuf; 4],
+        entries: &[&MergeEntry],
+        seed: MergeSeed,
+        journal: &mut Option<Journal>,
+    ) -> Result<i32, Error> {
+        let mut code = EXIT_SUCCESS;
+        for entry in entries {
+            // result with conflicts left by previous session is merged on
+            let resumed = is_skipped(journal.as_ref(), &entry.path);
+            let seed = if resumed { MergeSeed::Merged } else { seed };
+            // conflicts of automatic merge are the initial result in editor
+            let mut seed_text = None;
+            if (self.auto_merge || self.structural) && !resumed {
+                match self.auto_merge_entry(dirs, entry)? {
+                    Some((text, _)) => seed_text = Some(text),
+                    None => {
+                        self.record(journal, &entry.path, FileState::AutoMerged)?;
+                        continue;
+                    }
+                }
+            }
+            let [base_cfg, second_cfg, old_vendor_cfg, merged] =
+                dirs.each_ref().map(|dir| dir.join(&entry.path));
+            self.create_parent(&merged)
+                .map_err(|source| Error::WriteBack {
+                    path: merged.clone(),
+                    source,
+                })?;
+            let file_type = self.file_type(&base_cfg)?;
+            let files = MergeFiles {
+                base_cfg,
+                second_cfg,
+                // empty ancestor of file added on both sides
+                old_vendor_cfg: Some(old_vendor_cfg).filter(|_| entry.ours != Change::Added),
+                merged,
+                titles: [0, 1, 2].map(|i| Some(tree::title(&dirs[i], &entry.path))),
+            };
+            self.vscmd.clear_args();
+            let file_code = self.merge_in_editor(files, file_type, seed, seed_text)?;
+            // left pending if editor failed
+            if file_code == EXIT_EDITOR_FAILED {
+                return Ok(file_code);
+            }
+            let state = match file_code {
+                EXIT_SUCCESS => FileState::Merged,
+                _ => FileState::Skipped,
+            };
+            self.record(journal, &entry.path, state)?;
+            if code == EXIT_SUCCESS {
+                code = file_code;
+            }
+        }
+        Ok(code)
+    }
+
+    /// merge `entries` of trees `dirs` in one editor window: results (with conflict markers with
+    /// `auto_merge`, `seed` files otherwise) are staged in workspace by relative path and written
+    /// back after editor is closed
+    fn merge_in_workspace(
+        &mut self,
+        dirs: &[PathBuf; 4],
+        entries: &[&MergeEntry],
+        seed: MergeSeed,
+        journal: &mut Option<Journal>,
+    ) -> Result<i32, Error> {
+        let extension = self.extension.clone();
+        let workspace = self.workspace(&extension, FileType::Bsl)?;
+        let mut tx = self.transaction(&workspace);
+        // entry, staged file, result file and its encoding
+        let mut staged = vec![];
+        for entry in entries {
+            let merged = dirs[3].join(&entry.path);
+            // result with conflicts left by previous session is merged on
+            let text = if is_skipped(journal.as_ref(), &entry.path) && merged.is_file() {
+                let (text, encoding) = read_text(&merged)?;
+                Some((text, self.output_encoding.unwrap_or(encoding)))
+            } else if self.auto_merge || self.structural {
+                self.auto_merge_entry(dirs, entry)?
+            } else {
+                self.create_parent(&merged)
+                    .map_err(|source| Error::WriteBack {
+                        path: merged.clone(),
+                        source,
+                    })?;
+                let (text, encoding) = read_text(&dirs[seed_index(seed)].join(&entry.path))?;
+                Some((text, self.output_encoding.unwrap_or(encoding)))
+            };
+            let Some((text, output_encoding)) = text else {
+                self.record(journal, &entry.path, FileState::AutoMerged)?;
+                continue;
+            };
+            let path = workspace.path().join(&entry.path);
+            let error = |source| Error::Stage {
+                from: dirs[0].join(&entry.path),
+                to: path.clone(),
+                source,
+            };
+            self.create_parent(&path).map_err(error)?;
+            tx.write(&path, &encoding::encode(&text, TextEncoding::Utf8Bom))
+                .map_err(error)?;
+            staged.push((entry, path, merged, output_encoding));
+        }
+        if staged.is_empty() {
+            self.finish(tx, workspace)?;
+            return Ok(EXIT_SUCCESS);
+        }
+
+        let args = to_os_strings(self.backend.workspace_args(workspace.path().as_os_str()));
+        if self.dry_run {
+            self.print_launch(&args);
+            for (_, path, merged, encoding) in &staged {
+                print_write_back(path, merged, *encoding);
+            }
+            self.finish(tx, workspace)?;
+            return Ok(EXIT_SUCCESS);
+        }
+        self.vscmd.clear_args();
+        self.vscmd.args(&self.editor_args).args(args);
+        let status = self.launch_editor()?;
+        let mut left = vec![];
+        // nothing is written back if editor failed, files are left pending
+        if !interrupted() && status.success() {
+            for (entry, path, merged, encoding) in &staged {
+                let markers = find_markers(&read_text(path)?.0);
+                write_back(path, merged, *encoding)?;
+                if markers.is_empty() {
+                    self.record(journal, &entry.path, FileState::Merged)?;
+                } else {
+                    self.record(journal, &entry.path, FileState::Skipped)?;
+                    left.push((merged, markers));
+                }
+            }
+        }
+        self.finish(tx, workspace)?;
+        check_interrupted()?;
+
+        for (merged, markers) in &left {
+            report_markers(merged, markers);
+        }
+        if !left.is_empty() {
+            return Ok(EXIT_CONFLICTS);
+        }
+        Ok(exit::editor_code(status))
+    }
+
+    /// merge `entry` of trees `dirs` by lines: `None` if result is written, text with conflict
+    /// markers and encoding of result otherwise
+    fn auto_merge_entry(
+        &self,
+        dirs: &[PathBuf; 4],
+        entry: &MergeEntry,
+    ) -> Result<Option<(String, TextEncoding)>, Error> {
+        let [base_cfg, second_cfg, old_vendor_cfg, merged] =
+            dirs.each_ref().map(|dir| dir.join(&entry.path));
+        let (ours, base_encoding) = read_text(&base_cfg)?;
+        let (theirs, _) = read_text(&second_cfg)?;
+        let ancestor = match entry.ours {
+            Change::Added => String::new(),
+            _ => read_text(&old_vendor_cfg)?.0,
+        };
+        let name = entry.path.file_name().unwrap_or_default().to_string_lossy();
+        let file_type = filetype::classify(&name, &ours, &self.file_types);
+        let titles = [0, 1, 2].map(|i| tree::title(&dirs[i], &entry.path));
+        let labels = Labels {
+            ours: &titles[0],
+            theirs: &titles[1],
+            ancestor: &titles[2],
+        };
+        let output_encoding = self.output_encoding.unwrap_or(base_encoding);
+        self.create_parent(&merged)
+            .map_err(|source| Error::WriteBack {
+                path: merged.clone(),
+                source,
+            })?;
+        match self.merge_texts(file_type, [&ancestor, &ours, &theirs], labels) {
+            Merged::Clean(text) => {
+                self.write_merged(&merged, &text, output_encoding)?;
+                Ok(None)
+            }
+            Merged::Conflicts { text, .. } => Ok(Some((text, output_encoding))),
+        }
+    }
+
+    /// save `state` of file at relative `path` in `journal` if any (not in dry run)
+    fn record(
+        &self,
+        journal: &mut Option<Journal>,
+        path: &Path,
+        state: FileState,
+    ) -> Result<(), Error> {
+        let Some(journal) = journal else {
+            return Ok(());
+        };
+        journal.set(path, state);
+        if self.dry_run {
+            return Ok(());
+        }
+        journal.save()
+    }
+
+    /// print progress of merge from `journal` file: number of files in each state, files left
+    fn print_status(&self, journal: &Path) -> Result<i32, Error> {
+        let journal = Journal::load(journal)?.ok_or_else(|| Error::Journal {
+            path: journal.to_path_buf(),
+            source: io::ErrorKind::NotFound.into(),
+        })?;
+        let counts = journal.counts();
+        for state in FileState::ALL {
+            let count = counts.get(&state).copied().unwrap_or_default();
+            println!("{}: {count}", translate(i18n::lang(), state.name()));
+        }
+        let mut finished = true;
+        for (path, state) in &journal.files {
+            if matches!(state, FileState::Pending | FileState::Skipped) {
+                let name = translate(i18n::lang(), state.name());
+                println!("{name} {}", path.display());
+                finished = false;
+            }
+        }
+        Ok(if finished {
+            EXIT_SUCCESS
+        } else {
+            EXIT_CONFLICTS
+        })
+    }
+
+    /// copy `source` file of tree to `merged` (nothing if it is the same file), remove `merged`
+    /// if there is no source, only printed in dry run
+    fn take(&self, source: Option<&Path>, merged: &Path) -> Result<(), Error> {
+        match source {
+            Some(source) if source == merged => Ok(()),
+            Some(source) if self.dry_run => {
+                let args: [(&str, &dyn Display); 2] =
+                    [("from", &source.display()), ("to", &merged.display())];
+                println!("{}", tr("copy {from} -> {to}", &args));
+                Ok(())
+            }
+            Some(source) => {
+                let error = |source| Error::WriteBack {
+                    path: merged.to_path_buf(),
+                    source,
+                };
+                self.create_parent(merged).map_err(error)?;
+                fs::copy(source, merged).map_err(error)?;
+                Ok(())
+            }
+            None if merged.is_file() => self.remove_file(merged),
+            None => Ok(()),
+        }
+    }
+
+    /// create parent directories of `path` (not in dry run)
+    fn create_parent(&self, path: &Path) -> io::Result<()> {
+        match path.parent() {
+            Some(parent) if !self.dry_run => fs::create_dir_all(parent),
+            _ => Ok(()),
+        }
+    }
+
+    /// staging directory for files of `file_type`, only printed in dry run
+    fn workspace(&self, extension: &str, file_type: FileType) -> Result<Workspace, Error> {
+        if self.dry_run {
+            Ok(Workspace::plan(self.staging_dir.as_deref()))
+        } else {
+            Workspace::new(
+                self.staging_dir.as_deref(),
+                extension,
+                file_type.language_id(),
+            )
+        }
+    }
+
+    /// staging file operations into `workspace`, only printed in dry run
+    fn transaction(&self, workspace: &Workspace) -> Transaction {
+        let tx = if self.dry_run {
+            Transaction::dry_run()
+        } else {
+            Transaction::new()
+        };
+        tx.keeping(workspace)
+    }
+
+    /// print editor command line with `args` (dry run)
+    fn print_launch(&self, args: &[OsString]) {
+        let program = self.vscmd.get_program();
+        let editor_args = self.editor_args.iter().map(OsStr::new);
+        let args = editor_args.chain(args.iter().map(OsString::as_os_str));
+        let line: Vec<String> = iter::once(program).chain(args).map(quote).collect();
+        println!(
+            "{}",
+            tr("launch {command}", &[("command", &line.join(" "))])
+        );
+    }
+
+    /// merge `texts` (ancestor, ours, theirs) by lines, by methods of BSL modules if `structural`
+    fn merge_texts(&self, file_type: FileType, texts: [&str; 3], labels: Labels) -> Merged {
+        let [ancestor, ours, theirs] = texts;
+        let structural = if self.structural && file_type == FileType::Bsl {
+            merge3::merge_structural(ancestor, ours, theirs, labels)
+                .map_err(|err| {
+                    eprintln!(
+                        "{}",
+                        tr(
+                            "structural merge failed ({error}), merging lines",
+                            &[("error", &err)]
+                        )
+                    )
+                })
+                .ok()
+        } else {
+            None
+        };
+        structural.unwrap_or_else(|| merge3::merge(ancestor, ours, theirs, labels))
+    }
+
+    /// write merge result `text` in `encoding`, only printed in dry run
+    fn write_merged(&self, merged: &Path, text: &str, encoding: TextEncoding) -> Result<(), Error> {
+        if self.dry_run {
+            let encoding = format!("{encoding:?}");
+            let args: [(&str, &dyn Display); 2] =
+                [("path", &merged.display()), ("encoding", &encoding)];
+            println!("{}", tr("write {path} ({encoding})", &args));
+            return Ok(());
+        }
+        fs::write(merged, encoding::encode(text, encoding)).map_err(|source| Error::WriteBack {
+            path: merged.to_path_buf(),
+            source,
+        })
+    }
+
+    /// remove source or result file, only printed in dry run
+    fn remove_file(&self, path: &Path) -> Result<(), Error> {
+        if self.dry_run {
+            println!("{}", tr("remove {path}", &[("path", &path.display())]));
+            return Ok(());
+        }
+        fs::remove_file(path).map_err(|source| Error::Cleanup {
+            path: path.to_path_buf(),
+            source,
+        })
+    }
+
+    /// type of file from the platform by its name and content
+    fn file_type(&self, path: &Path) -> Result<FileType, Error> {
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        let (text, _) = read_text(path)?;
+        Ok(filetype::classify(&name, &text, &self.file_types))
+    }
+
+    /// profile selected by `--profile` or config, empty if none
+    fn profile(&self) -> Result<&Profile, Error> {
+        static NO_PROFILE: Profile = Profile {
+            merge: None,
+            diff: None,
+            merge_args: None,
+            diff_args: None,
+        };
+        match self.profile.as_deref() {
+            Some(name) => self
+                .profiles
+                .get(name)
+                .ok_or_else(|| Error::ProfileNotFound {
+                    name: name.to_string(),
+                }),
+            None => Ok(&NO_PROFILE),
+        }
+    }
+
+    /// launch editor with args given to `vscmd` and wait for it
+    fn launch_editor(&mut self) -> Result<ExitStatus, Error> {
+        self.vscmd.status().map_err(|source| Error::Launch {
+            program: self.vscmd.get_program().into(),
+            source,
+        })
+    }
+
+    /// keep moved source files if they are to be removed, otherwise move them back, then remove
+    /// `workspace` (kept with the files left in it if they can't be moved back)
+    fn finish(&self, mut tx: Transaction, workspace: Workspace) -> Result<(), Error> {
+        if self.rename_files && self.remove_files {
+            tx.commit();
+        } else if let Err(source) = tx.rollback() {
+            return Err(Error::StagingKept {
+                dir: workspace.path().to_path_buf(),
+                source: Box::new(source),
+            });
+        }
+        workspace.close()
+    }
+
+    /// do the action, returns exit code (see `exit`), can be called once
+    pub fn run(&mut self) -> Result<i32, Error> {
+        let mut action = self.action.take().ok_or(Error::AlreadyRun)?;
+        action.apply_profile(self.profile()?)?;
+        let cwd = env::current_dir().map_err(|source| Error::CurrentDir { source })?;
+        action.normalize_paths(&cwd);
+        info!("{action:?}");
+        match action {
+            Action::Diff {
+                base_cfg,
+                second_cfg,
+                base_title,
+                second_title,
+                output: Some(format),
+            } => self.print_diff(base_cfg, second_cfg, [base_title, second_title], format),
+            Action::Diff {
+                base_cfg,
+                second_cfg,
+                base_title,
+                second_title,
+                output: None,
+            } => self.command_diff(base_cfg, second_cfg, [base_title, second_title]),
+            Action::Merge {
+                base_cfg,
+                second_cfg,
+                old_vendor_cfg,
+                merged,
+                from_second,
+                base_title,
+                second_title,
+                old_vendor_title,
+            } => {
+                let seed = if from_second {
+                    MergeSeed::Second
+                } else {
+                    self.merge_seed
+                };
+                let files = MergeFiles {
+                    base_cfg,
+                    second_cfg,
+                    old_vendor_cfg: Some(old_vendor_cfg),
+                    merged,
+                    titles: [base_title, second_title, old_vendor_title],
+                };
+                self.command_merge(files, seed)
+            }
+            Action::GitMerge {
+                local,
+                remote,
+                base,
+                merged,
+            } => {
+                let files = MergeFiles {
+                    base_cfg: local,
+                    second_cfg: remote,
+                    old_vendor_cfg: Some(base),
+                    merged,
+                    titles: [None, None, None],
+                };
+                self.command_merge(files, MergeSeed::Merged)
+            }
+            Action::DiffDir {
+                base_dir,
+                second_dir,
+                list,
+            } => self.command_diff_dir(base_dir, second_dir, list),
+            Action::MergeDir {
+                base_dir,
+                second_dir,
+                old_vendor_dir,
+                merged_dir,
+                from_second,
+                workspace,
+                journal,
+            } => {
+                let seed = if from_second {
+                    MergeSeed::Second
+                } else {
+                    self.merge_seed
+                };
+                let dirs = [base_dir, second_dir, old_vendor_dir, merged_dir];
+                self.command_merge_dir(dirs, seed, workspace, journal)
+            }
+            Action::Status { journal } => self.print_status(&journal),
+            Action::GitDiff { local, remote } => self.command_diff(local, remote, [None, None]),
+            Action::InstallGit {
+                name,
+                file,
+                set_default,
+            } => {
+                let exe = env::current_exe().map_err(|source| Error::CurrentExe { source })?;
+                let entries = git::config_entries(&name, &exe, set_default);
+                git::install(&entries, file.as_deref())?;
+                Ok(0)
+            }
+        }
+    }
+}
+
+/// options of `Program`
+pub struct ProgramBuilder<C: WrappedCommand> {
+    program: Program<C>,
+}
+
+impl<C: WrappedCommand> ProgramBuilder<C> {
+    /// remove source files moved by `rename_files` (staged copies are always removed)
+    pub fn remove_files(mut self, remove_files: bool) -> Self {
+        self.program.remove_files = remove_files;
+        self
+    }
+
+    /// move source files to staging directory instead of copy
+    pub fn rename_files(mut self, rename_files: bool) -> Self {
+        self.program.rename_files = rename_files;
+        self
+    }
+
+    /// initial content of merge result
+    pub fn merge_seed(mut self, merge_seed: MergeSeed) -> Self {
+        self.program.merge_seed = merge_seed;
+        self
+    }
+
+    /// diff/merge editor (args of `vscmd`)
+    pub fn backend(mut self, backend: Backend) -> Self {
+        self.program.backend = backend;
+        self
+    }
+
+    /// extra args for editor (before diff/merge args)
+    pub fn editor_args(mut self, editor_args: Vec<String>) -> Self {
+        self.program.editor_args = editor_args;
+        self
+    }
+
+    /// filename extension for syntax highlights of modules
+    pub fn extension(mut self, extension: impl Into<String>) -> Self {
+        self.program.extension = extension.into();
+        self
+    }
+
+    /// file type by marker in file name, before default markers
+    pub fn file_types(mut self, file_types: Vec<(String, FileType)>) -> Self {
+        self.program.file_types = file_types;
+        self
+    }
+
+    /// encoding of merge result, `None` is same as source
+    pub fn output_encoding(mut self, output_encoding: Option<TextEncoding>) -> Self {
+        self.program.output_encoding = output_encoding;
+        self
+    }
+
+    /// directory for staging directories, `None` is system temp dir
+    pub fn staging_dir(mut self, staging_dir: Option<PathBuf>) -> Self {
+        self.program.staging_dir = staging_dir;
+        self
+    }
+
+    /// reopen editor if merge result has conflict markers
+    pub fn reopen_on_conflicts(mut self, reopen_on_conflicts: bool) -> Self {
+        self.program.reopen_on_conflicts = reopen_on_conflicts;
+        self
+    }
+
+    /// merge non-conflicting changes without editor
+    pub fn auto_merge(mut self, auto_merge: bool) -> Self {
+        self.program.auto_merge = auto_merge;
+        self
+    }
+
+    /// merge BSL methods matched by name
+    pub fn structural(mut self, structural: bool) -> Self {
+        self.program.structural = structural;
+        self
+    }
+
+    /// exit code for merge result not changed in editor, `None` if not detected
+    pub fn unchanged_exit_code(mut self, unchanged_exit_code: Option<i32>) -> Self {
+        self.program.unchanged_exit_code = unchanged_exit_code;
+        self
+    }
+
+    /// name of profile from `profiles`
+    pub fn profile(mut self, profile: Option<String>) -> Self {
+        self.program.profile = profile;
+        self
+    }
+
+    /// args order of callers and editors by name
+    pub fn profiles(mut self, profiles: HashMap<String, Profile>) -> Self {
+        self.program.profiles = profiles;
+        self
+    }
+
+    /// print operations instead of doing them
+    pub fn dry_run(mut self, dry_run: bool) -> Self {
+        self.program.dry_run = dry_run;
+        self
+    }
+
+    pub fn build(self) -> Program<C> {
+        self.program
+    }
+}
+
+/// value of CLI flag `--x` / `--no-x`, `None` if neither is given
+fn flag(yes: bool, no: bool) -> Option<bool> {
+    (yes || no).then_some(yes)
+}
+
+/// pairs of role and its file
+fn zip<const N: usize>(roles: [Role; N], values: [&OsStr; N]) -> Vec<(Role, &OsStr)> {
+    roles.into_iter().zip(values).collect()
+}
+
+fn to_os_strings(args: Vec<&OsStr>) -> Vec<OsString> {
+    args.into_iter().map(OsString::from).collect()
+}
+
+/// current content of `merged` if it is the `seed` (git mergetool)
+fn merged_seed(merged: &Path, seed: MergeSeed) -> Result<Option<Vec<u8>>, Error> {
+    match seed {
+        MergeSeed::Merged if merged.is_file() => read_file(merged).map(Some),
+        _ => Ok(None),
+    }
+}
+
+/// file at relative `path` is left with conflicts (or unchanged) by previous session of `journal`
+fn is_skipped(journal: Option<&Journal>, path: &Path) -> bool {
+    journal.and_then(|journal| journal.state(path)) == Some(FileState::Skipped)
+}
+
+/// index of source file (base or second) copied as initial merge result
+fn seed_index(seed: MergeSeed) -> usize {
+    match seed {
+        MergeSeed::Second => 1,
+        _ => 0,
+    }
+}
+
+/// `arg` of command line as is, quoted if it has spaces (or is empty)
+fn quote(arg: &OsStr) -> String {
+    let arg = arg.to_string_lossy();
+    if arg.is_empty() || arg.contains(char::is_whitespace) {
+        format!("\"{arg}\"")
+    } else {
+        arg.into_owned()
+    }
+}
+
+/// print lines of conflict `markers` left in `merged`
+fn report_markers(merged: &Path, markers: &[usize]) {
+    let lines: Vec<String> = markers.iter().map(usize::to_string).collect();
+    let args: [(&str, &dyn Display); 2] =
+        [("path", &merged.display()), ("lines", &lines.join(", "))];
+    eprintln!(
+        "{}",
+        tr("conflict markers left in {path} at lines {lines}", &args)
+    );
+}
+
+/// print planned write back of `staged` result to `merged` (dry run)
+fn print_write_back(staged: &Path, merged: &Path, encoding: TextEncoding) {
+    let encoding = format!("{encoding:?}");
+    let args: [(&str, &dyn Display); 3] = [
+        ("from", &staged.display()),
+        ("to", &merged.display()),
+        ("encoding", &encoding),
+    ];
+    println!("{}", tr("write back {from} -> {to} ({encoding})", &args));
+}
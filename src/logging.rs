@@ -0,0 +1,125 @@
+use log::{LevelFilter, Log, Metadata, Record};
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{self, Write},
+    path::{Path, PathBuf},
+    process,
+    sync::Mutex,
+};
+use time::{format_description::well_known::Rfc3339, OffsetDateTime};
+
+use crate::config::APP_DIR_NAME;
+use crate::error::Error;
+
+/// log file name in log dir
+pub const LOG_FILE: &str = "adapter.log";
+/// log file is rotated when it is bigger
+pub const MAX_LOG_SIZE: u64 = 1024 * 1024;
+/// number of rotated files kept (`adapter.log.1` is the newest)
+pub const MAX_LOG_FILES: usize = 5;
+
+/// default log dir in user's data dir
+pub fn default_log_dir() -> Option<PathBuf> {
+    Some(dirs::data_local_dir()?.join(APP_DIR_NAME).join("logs"))
+}
+
+/// `-v` count to level: info by default, then debug and trace
+pub fn level(verbose: u8) -> LevelFilter {
+    match verbose {
+        0 => LevelFilter::Info,
+        1 => LevelFilter::Debug,
+        _ => LevelFilter::Trace,
+    }
+}
+
+/// `dir/name` with rotation suffix (0 is the current file)
+fn numbered(dir: &Path, name: &str, n: usize) -> PathBuf {
+    match n {
+        0 => dir.join(name),
+        n => dir.join(format!("{name}.{n}")),
+    }
+}
+
+/// shift `name` -> `name.1` -> ... if it is bigger than `max_size`, keeping `keep` old files
+pub fn rotate(dir: &Path, name: &str, max_size: u64, keep: usize) -> io::Result<()> {
+    let current = dir.join(name);
+    match fs::metadata(&current) {
+        Ok(meta) if meta.len() > max_size => {}
+        _ => return Ok(()),
+    }
+    // the oldest file is replaced
+    for n in (0..keep).rev() {
+        let from = numbered(dir, name, n);
+        if from.exists() {
+            fs::rename(&from, numbered(dir, name, n + 1))?;
+        }
+    }
+    Ok(())
+}
+
+/// appends records to log file: time, process id, level, message
+pub struct FileLogger {
+    level: LevelFilter,
+    file: Mutex<File>,
+}
+
+impl FileLogger {
+    /// rotate and open log file in `dir`
+    pub fn open(dir: &Path, level: LevelFilter) -> io::Result<Self> {
+        fs::create_dir_all(dir)?;
+        rotate(dir, LOG_FILE, MAX_LOG_SIZE, MAX_LOG_FILES)?;
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(dir.join(LOG_FILE))?;
+        Ok(Self {
+            level,
+            file: Mutex::new(file),
+        })
+    }
+}
+
+impl Log for FileLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= self.level
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        let now = OffsetDateTime::now_local().unwrap_or_else(|_| OffsetDateTime::now_utc());
+        let now = now.format(&Rfc3339).unwrap_or_default();
+        if let Ok(mut file) = self.file.lock() {
+            let pid = process::id();
+            let _ = writeln!(
+                file,
+                "{now} [{pid}] {:<5} {}",
+                record.level(),
+                record.args()
+            );
+        }
+    }
+
+    fn flush(&self) {
+        if let Ok(mut file) = self.file.lock() {
+            let _ = file.flush();
+        }
+    }
+}
+
+/// log into `dir` with `level` for the rest of the process
+pub fn init(dir: &Path, level: LevelFilter) -> Result<(), Error> {
+    if level == LevelFilter::Off {
+        return Ok(());
+    }
+    let error = |source| Error::Log {
+        dir: dir.to_path_buf(),
+        source,
+    };
+    let logger = FileLogger::open(dir, level).map_err(error)?;
+    log::set_boxed_logger(Box::new(logger))
+        .map_err(|err| error(io::Error::new(io::ErrorKind::AlreadyExists, err)))?;
+    log::set_max_level(level);
+    Ok(())
+}
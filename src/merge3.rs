@@ -0,0 +1,244 @@
+use anyhow::{bail, Result};
+use similar::{capture_diff_slices, Algorithm, DiffOp};
+use std::collections::HashMap;
+
+use crate::bsl::{self, Item};
+
+/// labels after conflict markers
+#[derive(Debug, Clone, Copy)]
+pub struct Labels<'a> {
+    /// %baseCfg
+    pub ours: &'a str,
+    /// %oldVendorCfg
+    pub ancestor: &'a str,
+    /// %secondCfg
+    pub theirs: &'a str,
+}
+
+/// result of three-way merge
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Merged {
+    /// all changes are merged
+    Clean(String),
+    /// text with conflict markers and number of conflicts
+    Conflicts { text: String, count: usize },
+}
+
+/// lines with line endings
+fn split_lines(text: &str) -> Vec<&str> {
+    text.split_inclusive('\n').collect()
+}
+
+/// for each line of `old` index of the same line in `new`, if not changed
+fn matches(old: &[&str], new: &[&str]) -> Vec<Option<usize>> {
+    let mut result = vec![None; old.len()];
+    for op in capture_diff_slices(Algorithm::Patience, old, new) {
+        if let DiffOp::Equal {
+            old_index,
+            new_index,
+            len,
+        } = op
+        {
+            for i in 0..len {
+                result[old_index + i] = Some(new_index + i);
+            }
+        }
+    }
+    result
+}
+
+/// line-based merge of changes from `ancestor` to `ours` and to `theirs` (diff3)
+pub fn merge(ancestor: &str, ours: &str, theirs: &str, labels: Labels) -> Merged {
+    let (o, a, b) = (
+        split_lines(ancestor),
+        split_lines(ours),
+        split_lines(theirs),
+    );
+    let (ma, mb) = (matches(&o, &a), matches(&o, &b));
+    // markers use line ending of our file
+    let eol = if ours.contains("\r\n") { "\r\n" } else { "\n" };
+
+    let mut text = String::with_capacity(ours.len().max(theirs.len()));
+    let mut count = 0;
+    let (mut io, mut ia, mut ib) = (0, 0, 0);
+    loop {
+        // stable lines: not changed in both
+        while io < o.len() && ma[io] == Some(ia) && mb[io] == Some(ib) {
+            text.push_str(o[io]);
+            (io, ia, ib) = (io + 1, ia + 1, ib + 1);
+        }
+        // next line of ancestor kept in both, or end of files
+        let next = (io..o.len()).find_map(|j| Some((j, ma[j]?, mb[j]?)));
+        let (jo, ja, jb) = next.unwrap_or((o.len(), a.len(), b.len()));
+        let (co, ca, cb) = (&o[io..jo], &a[ia..ja], &b[ib..jb]);
+        if co.is_empty() && ca.is_empty() && cb.is_empty() {
+            break;
+        }
+        if ca == co || ca == cb {
+            cb.iter().for_each(|line| text.push_str(line));
+        } else if cb == co {
+            ca.iter().for_each(|line| text.push_str(line));
+        } else {
+            count += 1;
+            push_conflict(&mut text, eol, labels, ca, co, cb);
+        }
+        (io, ia, ib) = (jo, ja, jb);
+    }
+
+    if count == 0 {
+        Merged::Clean(text)
+    } else {
+        Merged::Conflicts { text, count }
+    }
+}
+
+/// conflict in diff3 style
+fn push_conflict(
+    text: &mut String,
+    eol: &str,
+    labels: Labels,
+    ours: &[&str],
+    ancestor: &[&str],
+    theirs: &[&str],
+) {
+    let mut push_side = |marker: &str, label: &str, lines: &[&str]| {
+        if !text.is_empty() && !text.ends_with('\n') {
+            text.push_str(eol);
+        }
+        text.push_str(marker);
+        if !label.is_empty() {
+            text.push(' ');
+            text.push_str(label);
+        }
+        text.push_str(eol);
+        lines.iter().for_each(|line| text.push_str(line));
+    };
+    push_side("<<<<<<<", labels.ours, ours);
+    push_side("|||||||", labels.ancestor, ancestor);
+    push_side("=======", "", theirs);
+    push_side(">>>>>>>", labels.theirs, &[]);
+}
+
+/// method texts by key and module text with methods replaced by placeholders
+struct Skeleton {
+    text: String,
+    methods: HashMap<String, String>,
+}
+
+/// prefix and suffix of placeholder line
+const PLACEHOLDER: char = '\u{1}';
+/// suffix of key of method changed on one side and deleted on the other
+const CHANGED: char = '*';
+
+impl Skeleton {
+    fn parse(text: &str) -> Result<Self> {
+        let mut skeleton = Skeleton {
+            text: String::new(),
+            methods: HashMap::new(),
+        };
+        for item in bsl::parse(text)? {
+            match item {
+                Item::Text(text) => skeleton.text.push_str(&text),
+                Item::Method(method) => {
+                    let key = method.key();
+                    if skeleton.methods.contains_key(&key) {
+                        bail!("method {} is declared twice", method.name);
+                    }
+                    skeleton.text.push(PLACEHOLDER);
+                    skeleton.text.push_str(&key);
+                    skeleton.text.push(PLACEHOLDER);
+                    skeleton.text.push('\n');
+                    skeleton.methods.insert(key, method.text);
+                }
+            }
+        }
+        Ok(skeleton)
+    }
+
+    /// mark methods changed here but deleted in `other` to make deletion a conflict
+    fn mark_changed(&mut self, ancestor: &Skeleton, other: &Skeleton) {
+        for (key, text) in self.methods.iter() {
+            let changed = ancestor.methods.get(key).is_some_and(|old| old != text);
+            if changed && !other.methods.contains_key(key) {
+                let placeholder = format!("{PLACEHOLDER}{key}{PLACEHOLDER}\n");
+                let marked = format!("{PLACEHOLDER}{key}{CHANGED}{PLACEHOLDER}\n");
+                self.text = self.text.replacen(&placeholder, &marked, 1);
+            }
+        }
+    }
+}
+
+/// side of conflict in merged text
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Side {
+    Merged,
+    Ours,
+    Ancestor,
+    Theirs,
+}
+
+/// merge of BSL modules: methods are matched by name, lines are merged inside methods only
+pub fn merge_structural(
+    ancestor: &str,
+    ours: &str,
+    theirs: &str,
+    labels: Labels,
+) -> Result<Merged> {
+    let o = Skeleton::parse(ancestor)?;
+    let mut a = Skeleton::parse(ours)?;
+    let mut b = Skeleton::parse(theirs)?;
+    a.mark_changed(&o, &b);
+    b.mark_changed(&o, &a);
+    let eol = if ours.contains("\r\n") { "\r\n" } else { "\n" };
+
+    let (skeleton, mut count) = match merge(&o.text, &a.text, &b.text, labels) {
+        Merged::Clean(text) => (text, 0),
+        Merged::Conflicts { text, count } => (text, count),
+    };
+    let mut text = String::with_capacity(skeleton.len() + ours.len());
+    let mut side = Side::Merged;
+    for line in skeleton.split_inclusive('\n') {
+        let chunk = match line.strip_prefix(PLACEHOLDER) {
+            Some(key) => {
+                let key = key.trim_end().trim_end_matches(PLACEHOLDER);
+                let key = key.strip_suffix(CHANGED).unwrap_or(key);
+                let [o, a, b] = [&o, &a, &b].map(|s| s.methods.get(key).map(String::as_str));
+                match (side, o, a, b) {
+                    (Side::Ours, ..) => a.unwrap_or_default().to_string(),
+                    (Side::Ancestor, ..) => o.unwrap_or_default().to_string(),
+                    (Side::Theirs, ..) => b.unwrap_or_default().to_string(),
+                    (Side::Merged, o, Some(a), Some(b)) if a != b => {
+                        match merge(o.unwrap_or_default(), a, b, labels) {
+                            Merged::Clean(text) => text,
+                            Merged::Conflicts { text, count: n } => {
+                                count += n;
+                                text
+                            }
+                        }
+                    }
+                    (Side::Merged, _, a, b) => a.or(b).unwrap_or_default().to_string(),
+                }
+            }
+            None => {
+                side = match line.get(..7) {
+                    Some("<<<<<<<") => Side::Ours,
+                    Some("|||||||") => Side::Ancestor,
+                    Some("=======") => Side::Theirs,
+                    Some(">>>>>>>") => Side::Merged,
+                    _ => side,
+                };
+                line.to_string()
+            }
+        };
+        if !chunk.is_empty() && !text.is_empty() && !text.ends_with('\n') {
+            text.push_str(eol);
+        }
+        text.push_str(&chunk);
+    }
+
+    Ok(if count == 0 {
+        Merged::Clean(text)
+    } else {
+        Merged::Conflicts { text, count }
+    })
+}
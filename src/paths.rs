@@ -0,0 +1,89 @@
+use std::path::{Component, Path, PathBuf, MAIN_SEPARATOR};
+
+/// path separators accepted from the platform
+#[cfg(windows)]
+pub const SEPARATORS: &[char] = &['\\', '/'];
+#[cfg(not(windows))]
+pub const SEPARATORS: &[char] = &['/'];
+
+/// remove whitespace and stray quotes around path (batch `%1` keeps them)
+pub fn strip_quotes(raw: &str) -> &str {
+    raw.trim().trim_matches('"').trim()
+}
+
+/// replace any of `separators` with `separator`, collapse repeated ones (UNC prefix is kept)
+pub fn normalize_separators(raw: &str, separators: &[char], separator: char) -> String {
+    let mut result = String::with_capacity(raw.len());
+    let unc = raw.len() > 2 && raw.chars().take(2).all(|c| separators.contains(&c));
+    if unc {
+        result.push(separator);
+    }
+    let mut prev_sep = false;
+    for c in raw.chars() {
+        if separators.contains(&c) {
+            if !prev_sep {
+                result.push(separator);
+            }
+            prev_sep = true;
+        } else {
+            result.push(c);
+            prev_sep = false;
+        }
+    }
+    result
+}
+
+/// resolve `.` and `..` without touching filesystem
+pub fn clean(path: &Path) -> PathBuf {
+    let mut result = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => match result.components().next_back() {
+                Some(Component::Normal(_)) => {
+                    result.pop();
+                }
+                Some(Component::RootDir | Component::Prefix(_)) => {}
+                _ => result.push(component),
+            },
+            _ => result.push(component),
+        }
+    }
+    result
+}
+
+/// path from the platform: without quotes, with native separators, absolute
+pub fn normalize(path: &Path, cwd: &Path) -> PathBuf {
+    let path = match path.to_str() {
+        Some(raw) => PathBuf::from(normalize_separators(
+            strip_quotes(raw),
+            SEPARATORS,
+            MAIN_SEPARATOR,
+        )),
+        None => path.to_path_buf(),
+    };
+    if path.is_absolute() {
+        clean(&path)
+    } else {
+        clean(&cwd.join(path))
+    }
+}
+
+/// max length of file name made from title
+pub const MAX_TITLE_LEN: usize = 100;
+
+/// file name from human-readable title, `None` if nothing left
+pub fn sanitize_file_name(title: &str) -> Option<String> {
+    let name: String = title
+        .chars()
+        .map(|c| match c {
+            '<' | '>' | ':' | '"' | '/' | '\\' | '|' | '?' | '*' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .take(MAX_TITLE_LEN)
+        .collect();
+    // Windows does not allow trailing dots and spaces
+    let name = name.trim().trim_end_matches(['.', ' ']);
+    (!name.is_empty()).then(|| name.to_string())
+}
@@ -0,0 +1,108 @@
+use serde::Deserialize;
+use std::ffi::{OsStr, OsString};
+use std::path::PathBuf;
+
+use crate::error::Error;
+
+/// file passed by the caller
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum Role {
+    /// %baseCfg
+    Base,
+    /// %secondCfg
+    Second,
+    /// %oldVendorCfg
+    OldVendor,
+    /// %merged
+    Merged,
+}
+
+impl Role {
+    /// placeholder in editor args template
+    pub fn placeholder(&self) -> &'static str {
+        match self {
+            Role::Base => "{base}",
+            Role::Second => "{second}",
+            Role::OldVendor => "{old-vendor}",
+            Role::Merged => "{merged}",
+        }
+    }
+}
+
+/// placeholder of staging directory in editor args template
+pub const WORKSPACE_PLACEHOLDER: &str = "{workspace}";
+
+pub const MERGE_ROLES: [Role; 4] = [Role::Base, Role::Second, Role::OldVendor, Role::Merged];
+pub const DIFF_ROLES: [Role; 2] = [Role::Base, Role::Second];
+
+/// how a caller passes files and how editor gets them (`[profiles.<name>]` in config)
+#[derive(Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Profile {
+    /// roles of positional args of `merge`, in order
+    pub merge: Option<Vec<Role>>,
+    /// roles of positional args of `diff`, in order
+    pub diff: Option<Vec<Role>>,
+    /// editor args for merge with placeholders (default is from backend)
+    pub merge_args: Option<Vec<String>>,
+    /// editor args for diff with placeholders (default is from backend)
+    pub diff_args: Option<Vec<String>>,
+}
+
+/// reorder positional `args` (passed in `order`) to `roles` order
+pub fn arrange<const N: usize>(
+    args: [PathBuf; N],
+    order: &[Role],
+    roles: [Role; N],
+) -> Result<[PathBuf; N], Error> {
+    let mut sorted = roles;
+    let mut given = order.to_vec();
+    sorted.sort_by_key(|r| *r as u8);
+    given.sort_by_key(|r| *r as u8);
+    if given != sorted {
+        let roles: Vec<&str> = roles.iter().map(Role::placeholder).collect();
+        return Err(Error::InvalidProfile {
+            roles: roles.join(", "),
+        });
+    }
+    let mut result = args.clone();
+    for (arg, role) in args.into_iter().zip(order) {
+        let i = roles.iter().position(|r| r == role).unwrap();
+        result[i] = arg;
+    }
+    Ok(result)
+}
+
+/// `template` with placeholders replaced with `workspace` and files of `roles` (any OS paths)
+pub fn expand(template: &[String], workspace: &OsStr, roles: &[(Role, &OsStr)]) -> Vec<OsString> {
+    let mut values = vec![(WORKSPACE_PLACEHOLDER, workspace)];
+    values.extend(
+        roles
+            .iter()
+            .map(|(role, value)| (role.placeholder(), *value)),
+    );
+    template
+        .iter()
+        .map(|arg| expand_arg(arg, &values))
+        .collect()
+}
+
+/// `arg` with each placeholder of `values` replaced, values are not expanded again
+fn expand_arg(arg: &str, values: &[(&str, &OsStr)]) -> OsString {
+    let mut result = OsString::new();
+    let mut rest = arg;
+    loop {
+        let next = values
+            .iter()
+            .filter_map(|(placeholder, value)| Some((rest.find(placeholder)?, placeholder, value)))
+            .min_by_key(|(i, ..)| *i);
+        let Some((i, placeholder, value)) = next else {
+            result.push(rest);
+            return result;
+        };
+        result.push(&rest[..i]);
+        result.push(value);
+        rest = &rest[i + placeholder.len()..];
+    }
+}
@@ -0,0 +1,247 @@
+use clap::ValueEnum;
+use serde::Serialize;
+use similar::{Algorithm, DiffTag, TextDiff};
+use std::io::{self, Write};
+
+use crate::bsl;
+
+/// unchanged lines around changes
+pub const CONTEXT_LINES: usize = 3;
+/// width of one side in side-by-side output
+pub const SIDE_WIDTH: usize = 60;
+
+/// diff output without editor
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DiffFormat {
+    /// unified diff (like `diff -u`)
+    Unified,
+    /// two columns (like `diff -y`)
+    SideBySide,
+    /// hunks as JSON
+    Json,
+}
+
+/// line of hunk
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct Line {
+    /// `equal`, `delete` or `insert`
+    pub tag: &'static str,
+    /// text without line ending
+    pub text: String,
+}
+
+/// changed lines with context
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct Hunk {
+    /// first line in old file (from 1)
+    pub old_start: usize,
+    pub old_lines: usize,
+    /// first line in new file (from 1)
+    pub new_start: usize,
+    pub new_lines: usize,
+    /// declaration of BSL method containing the first change
+    pub method: Option<String>,
+    pub lines: Vec<Line>,
+}
+
+/// JSON output
+#[derive(Serialize)]
+struct Report<'a> {
+    old: &'a str,
+    new: &'a str,
+    hunks: &'a [Hunk],
+}
+
+fn trim_eol(line: &str) -> &str {
+    line.trim_end_matches(['\r', '\n'])
+}
+
+/// `start,len` of unified hunk header
+fn range(start: usize, len: usize) -> String {
+    match len {
+        0 => format!("{},0", start.saturating_sub(1)),
+        1 => format!("{start}"),
+        _ => format!("{start},{len}"),
+    }
+}
+
+/// diff of `old` and `new` lines grouped into hunks
+pub fn hunks(old: &str, new: &str) -> Vec<Hunk> {
+    let diff = TextDiff::configure()
+        .algorithm(Algorithm::Patience)
+        .diff_lines(old, new);
+    let (old_lines, new_lines) = (diff.old_slices(), diff.new_slices());
+    let methods = bsl::enclosing_methods(old);
+
+    let mut hunks = vec![];
+    for group in diff.grouped_ops(CONTEXT_LINES) {
+        let (Some(first), Some(last)) = (group.first(), group.last()) else {
+            continue;
+        };
+        let changed = group.iter().find(|op| op.tag() != DiffTag::Equal);
+        let at = changed.map_or(0, |op| op.old_range().start);
+        let method = methods
+            .get(at)
+            .copied()
+            .flatten()
+            .or_else(|| methods.get(at.checked_sub(1)?).copied().flatten());
+
+        let mut lines = vec![];
+        let mut push = |tag, line: &str| {
+            lines.push(Line {
+                tag,
+                text: trim_eol(line).to_string(),
+            })
+        };
+        for op in group.iter() {
+            if op.tag() == DiffTag::Equal {
+                old_lines[op.old_range()]
+                    .iter()
+                    .for_each(|line| push("equal", line));
+            } else {
+                old_lines[op.old_range()]
+                    .iter()
+                    .for_each(|line| push("delete", line));
+                new_lines[op.new_range()]
+                    .iter()
+                    .for_each(|line| push("insert", line));
+            }
+        }
+        let (old_range, new_range) = (
+            first.old_range().start..last.old_range().end,
+            first.new_range().start..last.new_range().end,
+        );
+        hunks.push(Hunk {
+            old_start: old_range.start + 1,
+            old_lines: old_range.len(),
+            new_start: new_range.start + 1,
+            new_lines: new_range.len(),
+            method: method.map(str::to_string),
+            lines,
+        });
+    }
+    hunks
+}
+
+/// `@@ -1,2 +1,3 @@ Процедура А()`
+fn hunk_header(hunk: &Hunk) -> String {
+    let mut header = format!(
+        "@@ -{} +{} @@",
+        range(hunk.old_start, hunk.old_lines),
+        range(hunk.new_start, hunk.new_lines)
+    );
+    if let Some(method) = hunk.method.as_deref() {
+        header.push(' ');
+        header.push_str(method);
+    }
+    header
+}
+
+fn write_unified(
+    out: &mut impl Write,
+    old_label: &str,
+    new_label: &str,
+    old: &str,
+    new: &str,
+    hunks: &[Hunk],
+) -> io::Result<()> {
+    writeln!(out, "--- {old_label}")?;
+    writeln!(out, "+++ {new_label}")?;
+    let no_eol = |text: &str| !text.is_empty() && !text.ends_with('\n');
+    let (old_count, new_count) = (
+        old.split_inclusive('\n').count(),
+        new.split_inclusive('\n').count(),
+    );
+    for hunk in hunks {
+        writeln!(out, "{}", hunk_header(hunk))?;
+        let (mut old_line, mut new_line) = (hunk.old_start, hunk.new_start);
+        for line in hunk.lines.iter() {
+            let (prefix, last) = match line.tag {
+                "delete" => {
+                    old_line += 1;
+                    ('-', no_eol(old) && old_line - 1 == old_count)
+                }
+                "insert" => {
+                    new_line += 1;
+                    ('+', no_eol(new) && new_line - 1 == new_count)
+                }
+                _ => {
+                    old_line += 1;
+                    new_line += 1;
+                    (' ', no_eol(old) && old_line - 1 == old_count)
+                }
+            };
+            writeln!(out, "{prefix}{}", line.text)?;
+            if last {
+                writeln!(out, "\\ No newline at end of file")?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// text cut and padded to `SIDE_WIDTH` chars
+fn column(text: &str) -> String {
+    let text: String = text.chars().take(SIDE_WIDTH).collect();
+    format!("{text:<SIDE_WIDTH$}")
+}
+
+fn write_side_by_side(
+    out: &mut impl Write,
+    old_label: &str,
+    new_label: &str,
+    hunks: &[Hunk],
+) -> io::Result<()> {
+    writeln!(out, "{} | {}", column(old_label), new_label)?;
+    for hunk in hunks {
+        writeln!(out, "{}", hunk_header(hunk))?;
+        let mut lines = hunk.lines.iter().peekable();
+        while let Some(line) = lines.next() {
+            match line.tag {
+                "equal" => writeln!(out, "{}   {}", column(&line.text), line.text)?,
+                "insert" => writeln!(out, "{} > {}", column(""), line.text)?,
+                _ => {
+                    // deleted lines paired with following inserted ones
+                    let mut deleted = vec![line];
+                    while let Some(line) = lines.next_if(|l| l.tag == "delete") {
+                        deleted.push(line);
+                    }
+                    for old in deleted {
+                        match lines.next_if(|l| l.tag == "insert") {
+                            Some(new) => writeln!(out, "{} | {}", column(&old.text), new.text)?,
+                            None => writeln!(out, "{} <", column(&old.text))?,
+                        }
+                    }
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// print diff of `old` and `new` in `format`, returns `true` if they differ
+pub fn write_diff(
+    out: &mut impl Write,
+    format: DiffFormat,
+    old_label: &str,
+    new_label: &str,
+    old: &str,
+    new: &str,
+) -> io::Result<bool> {
+    let hunks = hunks(old, new);
+    match format {
+        DiffFormat::Unified if hunks.is_empty() => {}
+        DiffFormat::Unified => write_unified(out, old_label, new_label, old, new, &hunks)?,
+        DiffFormat::SideBySide => write_side_by_side(out, old_label, new_label, &hunks)?,
+        DiffFormat::Json => {
+            let report = Report {
+                old: old_label,
+                new: new_label,
+                hunks: &hunks,
+            };
+            serde_json::to_writer_pretty(&mut *out, &report)?;
+            writeln!(out)?;
+        }
+    }
+    Ok(!hunks.is_empty())
+}
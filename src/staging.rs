@@ -0,0 +1,359 @@
+use log::{debug, warn};
+use std::{
+    cell::{Cell, RefCell},
+    collections::HashSet,
+    env,
+    ffi::{OsStr, OsString},
+    fs, io,
+    path::{Path, PathBuf},
+    rc::Rc,
+    sync::atomic::{AtomicBool, Ordering},
+};
+use tempfile::TempDir;
+
+use crate::error::Error;
+use crate::i18n::tr;
+
+/// prefix of staging directory name
+pub const WORKSPACE_PREFIX: &str = "vscode-adapter-";
+/// VSCode workspace settings dir
+pub const VSCODE_DIR: &str = ".vscode";
+pub const VSCODE_SETTINGS_FILE: &str = "settings.json";
+/// language id of 1C:Enterprise script in VSCode
+pub const BSL_LANGUAGE_ID: &str = "bsl";
+
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+/// don't exit on Ctrl+C: wait for editor to exit and clean up
+pub fn handle_interrupts() -> Result<(), Error> {
+    ctrlc::set_handler(|| INTERRUPTED.store(true, Ordering::SeqCst))
+        .map_err(|source| Error::Interrupts { source })
+}
+
+/// Ctrl+C was pressed
+pub fn interrupted() -> bool {
+    INTERRUPTED.load(Ordering::SeqCst)
+}
+
+/// error if Ctrl+C was pressed
+pub fn check_interrupted() -> Result<(), Error> {
+    if interrupted() {
+        return Err(Error::Interrupted);
+    }
+    Ok(())
+}
+
+/// staging directory of one invocation, removed on drop
+pub struct Workspace {
+    /// `None` in dry run: directory is not created
+    dir: Option<TempDir>,
+    path: PathBuf,
+    /// paths given by `file_path` (files are not created in dry run)
+    reserved: RefCell<HashSet<PathBuf>>,
+    /// set by failed rollback of transaction (see `Transaction::keeping`): moved sources are
+    /// left in directory, it is not removed
+    keep: Rc<Cell<bool>>,
+}
+
+impl Workspace {
+    /// create in `root` (default is system temp dir) with settings for `extension` files
+    pub fn new(
+        root: Option<&Path>,
+        extension: &str,
+        language_id: Option<&str>,
+    ) -> Result<Self, Error> {
+        let root = root.map_or_else(env::temp_dir, Path::to_path_buf);
+        let error = |source| Error::Workspace {
+            dir: root.clone(),
+            source,
+        };
+        fs::create_dir_all(&root).map_err(error)?;
+        let dir = tempfile::Builder::new()
+            .prefix(WORKSPACE_PREFIX)
+            .tempdir_in(&root)
+            .map_err(error)?;
+        let workspace = Self {
+            path: dir.path().to_path_buf(),
+            dir: Some(dir),
+            reserved: Default::default(),
+            keep: Default::default(),
+        };
+        debug!("workspace {}", workspace.path().display());
+        workspace
+            .write_settings(extension, language_id)
+            .map_err(error)?;
+        Ok(workspace)
+    }
+
+    /// dry run: print planned directory in `root` instead of creating it
+    pub fn plan(root: Option<&Path>) -> Self {
+        let root = root.map_or_else(env::temp_dir, Path::to_path_buf);
+        let path = root.join(format!("{WORKSPACE_PREFIX}dry-run"));
+        println!(
+            "{}",
+            tr("create directory {path}", &[("path", &path.display())])
+        );
+        let settings = path.join(VSCODE_DIR).join(VSCODE_SETTINGS_FILE);
+        println!("{}", tr("write {path}", &[("path", &settings.display())]));
+        Self {
+            dir: None,
+            path,
+            reserved: Default::default(),
+            keep: Default::default(),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// path for `stem`.`extension` not used in workspace yet
+    pub fn file_path(&self, stem: &OsStr, extension: &str) -> PathBuf {
+        let name = |suffix: &str| {
+            let mut name = OsString::from(stem);
+            name.push(suffix);
+            name.push(".");
+            name.push(extension);
+            self.path().join(name)
+        };
+        let mut path = name("");
+        let mut n = 1;
+        let mut reserved = self.reserved.borrow_mut();
+        while path.exists() || reserved.contains(&path) {
+            n += 1;
+            path = name(&format!(" ({n})"));
+        }
+        reserved.insert(path.clone());
+        path
+    }
+
+    /// `.vscode/settings.json` to highlight staged files as `language_id` (if editor does not know it)
+    fn write_settings(&self, extension: &str, language_id: Option<&str>) -> io::Result<()> {
+        let dir = self.path().join(VSCODE_DIR);
+        fs::create_dir(&dir)?;
+        let associations = match language_id {
+            Some(language_id) => format!(
+                r#"
+    "files.associations": {{
+        "*.{extension}": "{language_id}"
+    }},"#
+            ),
+            None => String::new(),
+        };
+        let settings = format!(
+            r#"{{{associations}
+    "files.encoding": "utf8bom"
+}}
+"#
+        );
+        fs::write(dir.join(VSCODE_SETTINGS_FILE), settings)?;
+        Ok(())
+    }
+
+    /// directory is kept because rollback of its transaction failed
+    pub fn is_kept(&self) -> bool {
+        self.keep.get()
+    }
+
+    /// remove directory (unless it is kept), reporting errors
+    pub fn close(mut self) -> Result<(), Error> {
+        if self.is_kept() {
+            return Ok(());
+        }
+        debug!("remove workspace {}", self.path().display());
+        match self.dir.take() {
+            Some(dir) => dir.close().map_err(|source| Error::Cleanup {
+                path: self.path.clone(),
+                source,
+            })?,
+            None => println!(
+                "{}",
+                tr("remove directory {path}", &[("path", &self.path.display())])
+            ),
+        }
+        Ok(())
+    }
+}
+
+impl Drop for Workspace {
+    fn drop(&mut self) {
+        if !self.is_kept() {
+            return;
+        }
+        if let Some(dir) = self.dir.take() {
+            let path = dir.into_path();
+            warn!("keep workspace {}", path.display());
+        }
+    }
+}
+
+/// file operation done by `Transaction`
+#[derive(Debug)]
+enum Operation {
+    /// new file (copy or converted source)
+    Created(PathBuf),
+    /// source file moved to staging directory
+    Moved { from: PathBuf, to: PathBuf },
+}
+
+/// staging file operations, undone on `rollback` or on drop unless committed
+#[derive(Debug, Default)]
+pub struct Transaction {
+    ops: Vec<Operation>,
+    committed: bool,
+    /// operations are printed, not done
+    dry_run: bool,
+    /// flag of workspace to keep if rollback fails
+    keep: Option<Rc<Cell<bool>>>,
+}
+
+impl Transaction {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// print operations instead of doing them
+    pub fn dry_run() -> Self {
+        let mut tx = Self::new();
+        tx.dry_run = true;
+        tx
+    }
+
+    /// keep `workspace` if rollback fails, so moved sources left in it are not removed
+    pub fn keeping(mut self, workspace: &Workspace) -> Self {
+        self.keep = Some(workspace.keep.clone());
+        self
+    }
+
+    /// copy `src` to `dst`
+    pub fn copy(&mut self, src: &Path, dst: &Path) -> io::Result<()> {
+        if self.dry_run {
+            println!(
+                "{}",
+                tr(
+                    "copy {from} -> {to}",
+                    &[("from", &src.display()), ("to", &dst.display())]
+                )
+            );
+        } else {
+            fs::copy(src, dst)?;
+        }
+        debug!("staged copy {} -> {}", src.display(), dst.display());
+        self.ops.push(Operation::Created(dst.to_path_buf()));
+        Ok(())
+    }
+
+    /// write `contents` to new file `dst`
+    pub fn write(&mut self, dst: &Path, contents: &[u8]) -> io::Result<()> {
+        self.ops.push(Operation::Created(dst.to_path_buf()));
+        if self.dry_run {
+            println!("{}", tr("write {path}", &[("path", &dst.display())]));
+            return Ok(());
+        }
+        fs::write(dst, contents)?;
+        debug!("staged converted {}", dst.display());
+        Ok(())
+    }
+
+    /// move `src` to `dst`, moved back on rollback
+    pub fn move_file(&mut self, src: &Path, dst: &Path) -> io::Result<()> {
+        if self.dry_run {
+            println!(
+                "{}",
+                tr(
+                    "move {from} -> {to}",
+                    &[("from", &src.display()), ("to", &dst.display())]
+                )
+            );
+        } else {
+            move_file(src, dst)?;
+        }
+        debug!("staged move {} -> {}", src.display(), dst.display());
+        self.ops.push(Operation::Moved {
+            from: src.to_path_buf(),
+            to: dst.to_path_buf(),
+        });
+        Ok(())
+    }
+
+    /// undo all operations in reverse order, first error is returned after trying all
+    pub fn rollback(&mut self) -> Result<(), Error> {
+        let mut result = Ok(());
+        while let Some(op) = self.ops.pop() {
+            debug!("undo {op:?}");
+            let undone = match op {
+                Operation::Created(path) if self.dry_run => {
+                    println!("{}", tr("remove {path}", &[("path", &path.display())]));
+                    Ok(())
+                }
+                Operation::Moved { from, to } if self.dry_run => {
+                    println!(
+                        "{}",
+                        tr(
+                            "move back {from} -> {to}",
+                            &[("from", &to.display()), ("to", &from.display())]
+                        )
+                    );
+                    Ok(())
+                }
+                Operation::Created(path) if path.exists() => {
+                    fs::remove_file(&path).map_err(|source| Error::Cleanup { path, source })
+                }
+                Operation::Created(_) => Ok(()),
+                Operation::Moved { from, to } => {
+                    move_file(&to, &from).map_err(|source| Error::RenameBack {
+                        from: to,
+                        to: from,
+                        source,
+                    })
+                }
+            };
+            if let Err(err) = undone {
+                warn!("{err}");
+                if let Some(keep) = &self.keep {
+                    keep.set(true);
+                }
+                if result.is_ok() {
+                    result = Err(err);
+                }
+            }
+        }
+        result
+    }
+
+    /// keep all operations (moved files stay in staging directory)
+    pub fn commit(mut self) {
+        debug!("keep {:?}", self.ops);
+        if self.dry_run {
+            for op in &self.ops {
+                if let Operation::Moved { from, .. } = op {
+                    println!(
+                        "{}",
+                        tr(
+                            "remove {path} (moved to staging directory)",
+                            &[("path", &from.display())]
+                        )
+                    );
+                }
+            }
+        }
+        self.committed = true;
+    }
+}
+
+impl Drop for Transaction {
+    fn drop(&mut self) {
+        if !self.committed {
+            let _ = self.rollback();
+        }
+    }
+}
+
+/// rename, or copy and remove if `dst` is on other volume
+pub fn move_file(src: &Path, dst: &Path) -> io::Result<()> {
+    if fs::rename(src, dst).is_err() {
+        fs::copy(src, dst)?;
+        fs::remove_file(src)?;
+    }
+    Ok(())
+}
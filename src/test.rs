@@ -1,16 +1,44 @@
-use std::{ffi::OsStr, io, path::Path, process::ExitStatus};
-
+use std::{
+    ffi::{OsStr, OsString},
+    fs, io,
+    path::Path,
+    process::{self, ExitStatus},
+};
+
+use crate::editor::*;
 use crate::*;
 use tempfile::tempdir;
 
-struct TestComand {
+mod bsl;
+mod config;
+mod conflicts;
+mod discovery;
+mod encoding;
+mod error;
+mod exit;
+mod filetype;
+mod git;
+mod i18n;
+mod journal;
+mod logging;
+mod merge3;
+mod paths;
+mod profile;
+mod report;
+mod staging;
+mod tree;
+
+pub(crate) struct TestComand {
     inner: process::Command,
+    /// contents of existing files from args at launch
+    pub(crate) files: Vec<(PathBuf, Vec<u8>)>,
 }
 
 impl TestComand {
-    fn new() -> Self {
+    pub(crate) fn new() -> Self {
         Self {
             inner: process::Command::new("echo"),
+            files: vec![],
         }
     }
     fn into_iner(self) -> process::Command {
@@ -33,25 +61,38 @@ impl WrappedCommand for TestComand {
         use std::os::unix::process::ExitStatusExt;
         #[cfg(windows)]
         use std::os::windows::process::ExitStatusExt;
+        for arg in self.inner.get_args() {
+            let path = PathBuf::from(arg);
+            if path.is_file() {
+                let content = fs::read(&path)?;
+                self.files.push((path, content));
+            }
+        }
         Ok(ExitStatus::from_raw(0))
     }
+
+    fn clear_args(&mut self) {
+        self.inner = process::Command::new(self.inner.get_program());
+    }
+
+    fn get_program(&self) -> &OsStr {
+        self.inner.get_program()
+    }
 }
 
 impl<C: WrappedCommand> Program<C> {
     fn new_test(action: Action, vscmd: C, remove_files: bool, rename_files: bool) -> Self {
-        Self {
-            remove_files,
-            rename_files,
-            vscmd,
-            action: Some(action),
-        }
+        Program::builder(action, vscmd)
+            .remove_files(remove_files)
+            .rename_files(rename_files)
+            .build()
     }
     fn into_vscmd(self) -> C {
         self.vscmd
     }
 }
 
-fn prepare_merge(
+pub(crate) fn prepare_merge(
     base_cfg: &PathBuf,
     second_cfg: &PathBuf,
     old_vendor_cfg: &PathBuf,
@@ -73,6 +114,9 @@ fn prepare_merge(
             old_vendor_cfg: old_vendor_cfg.clone(),
             merged: merged.to_path_buf(),
             from_second,
+            base_title: None,
+            second_title: None,
+            old_vendor_title: None,
         },
         vscmd,
         remove_files,
@@ -80,7 +124,7 @@ fn prepare_merge(
     )
 }
 
-fn prepare_diff(
+pub(crate) fn prepare_diff(
     base_cfg: &PathBuf,
     second_cfg: &PathBuf,
     remove_files: bool,
@@ -95,6 +139,9 @@ fn prepare_diff(
         Action::Diff {
             base_cfg: base_cfg.clone(),
             second_cfg: second_cfg.clone(),
+            base_title: None,
+            second_title: None,
+            output: None,
         },
         vscmd,
         remove_files,
@@ -102,6 +149,63 @@ fn prepare_diff(
     )
 }
 
+/// staging workspace from VSCode args
+pub(crate) fn workspace_of(args: &[OsString]) -> PathBuf {
+    PathBuf::from(&args[COMMON_CODE_ARGS.len()])
+}
+
+/// check VSCode diff args, returns staged files
+fn check_diff_args(vscmd: &TestComand, dir: &Path) -> Vec<PathBuf> {
+    let actual: Vec<OsString> = vscmd.inner.get_args().map(OsStr::to_owned).collect();
+    let workspace = workspace_of(&actual);
+    let staged = vec![
+        workspace.join("base_cfg.bsl"),
+        workspace.join("second_cfg.bsl"),
+    ];
+
+    let mut expected: Vec<OsString> = vec![];
+    expected.extend(COMMON_CODE_ARGS.into_iter().map(OsString::from));
+    expected.push(workspace.clone().into_os_string());
+    expected.push(CODE_CMD_DIFF.into());
+    expected.extend(staged.iter().map(|p| p.clone().into_os_string()));
+    assert_eq!(expected, actual);
+
+    // workspace is removed and is not in platform's dir
+    assert!(!workspace.starts_with(dir));
+    assert!(fs::metadata(&workspace).is_err());
+    staged
+}
+
+/// check VSCode merge args, returns staged files
+fn check_merge_args(vscmd: &TestComand, dir: &Path) -> Vec<PathBuf> {
+    let actual: Vec<OsString> = vscmd.inner.get_args().map(OsStr::to_owned).collect();
+    let workspace = workspace_of(&actual);
+    let staged = vec![
+        workspace.join("base_cfg.bsl"),
+        workspace.join("second_cfg.bsl"),
+        workspace.join("old_vendor_cfg.bsl"),
+        workspace.join("merged.bsl"),
+    ];
+
+    let mut expected: Vec<OsString> = vec![];
+    expected.extend(COMMON_CODE_ARGS.into_iter().map(OsString::from));
+    expected.push(workspace.clone().into_os_string());
+    expected.push(CODE_CMD_MERGE.into());
+    expected.extend(staged.iter().map(|p| p.clone().into_os_string()));
+    assert_eq!(expected, actual);
+
+    // workspace is removed and is not in platform's dir
+    assert!(!workspace.starts_with(dir));
+    assert!(fs::metadata(&workspace).is_err());
+    staged
+}
+
+/// staged file content at editor launch
+fn staged_content(vscmd: &TestComand, path: &Path) -> String {
+    let (_, content) = vscmd.files.iter().find(|(p, _)| p == path).unwrap();
+    String::from_utf8(content.clone()).unwrap()
+}
+
 #[test]
 fn verify_cli() {
     use clap::CommandFactory;
@@ -127,25 +231,14 @@ fn test_cmd_diff() {
     assert!(fs::metadata(&base_cfg).is_ok());
     assert!(fs::metadata(&second_cfg).is_ok());
 
-    // check bsl files exists
-    assert!(fs::metadata(&base_cfg_bsl).is_ok());
-    assert!(fs::metadata(&second_cfg_bsl).is_ok());
+    // check bsl files not exists in platform's dir
+    assert!(fs::metadata(&base_cfg_bsl).is_err());
+    assert!(fs::metadata(&second_cfg_bsl).is_err());
 
     // check args
-    let vscmd = prog.into_vscmd().into_iner();
-    let mut expected: Vec<String> = vec![];
-    expected.extend(COMMON_CODE_ARGS.into_iter().map(|x| x.to_string()));
-    expected.extend_from_slice(&[
-        CODE_CMD_DIFF.to_string(),
-        base_cfg_bsl.into_os_string().into_string().unwrap(),
-        second_cfg_bsl.into_os_string().into_string().unwrap(),
-    ]);
-
-    let actual: Vec<String> = vscmd
-        .get_args()
-        .map(|x| x.to_str().unwrap().to_string())
-        .collect();
-    assert_eq!(expected, actual);
+    let vscmd = prog.into_vscmd();
+    let staged = check_diff_args(&vscmd, dir.path());
+    assert_eq!(vscmd.files.len(), staged.len());
 }
 
 /// test diff (with remove, no rename)
@@ -172,20 +265,7 @@ fn test_cmd_diff_r() {
     assert!(fs::metadata(&second_cfg_bsl).is_err());
 
     // check args
-    let vscmd = prog.into_vscmd().into_iner();
-    let mut expected: Vec<String> = vec![];
-    expected.extend(COMMON_CODE_ARGS.into_iter().map(|x| x.to_string()));
-    expected.extend_from_slice(&[
-        CODE_CMD_DIFF.to_string(),
-        base_cfg_bsl.into_os_string().into_string().unwrap(),
-        second_cfg_bsl.into_os_string().into_string().unwrap(),
-    ]);
-
-    let actual: Vec<String> = vscmd
-        .get_args()
-        .map(|x| x.to_str().unwrap().to_string())
-        .collect();
-    assert_eq!(expected, actual);
+    check_diff_args(&prog.into_vscmd(), dir.path());
 }
 
 /// test diff (no remove, with rename)
@@ -203,29 +283,16 @@ fn test_cmd_diff_n() {
     // check status code
     assert_eq!(prog.run().unwrap(), 0);
 
-    // check original files not exists
-    assert!(fs::metadata(&base_cfg).is_err());
-    assert!(fs::metadata(&second_cfg).is_err());
+    // check original files moved back
+    assert!(fs::metadata(&base_cfg).is_ok());
+    assert!(fs::metadata(&second_cfg).is_ok());
 
-    // check bsl files exists
-    assert!(fs::metadata(&base_cfg_bsl).is_ok());
-    assert!(fs::metadata(&second_cfg_bsl).is_ok());
+    // check bsl files not exists
+    assert!(fs::metadata(&base_cfg_bsl).is_err());
+    assert!(fs::metadata(&second_cfg_bsl).is_err());
 
     // check args
-    let vscmd = prog.into_vscmd().into_iner();
-    let mut expected: Vec<String> = vec![];
-    expected.extend(COMMON_CODE_ARGS.into_iter().map(|x| x.to_string()));
-    expected.extend_from_slice(&[
-        CODE_CMD_DIFF.to_string(),
-        base_cfg_bsl.into_os_string().into_string().unwrap(),
-        second_cfg_bsl.into_os_string().into_string().unwrap(),
-    ]);
-
-    let actual: Vec<String> = vscmd
-        .get_args()
-        .map(|x| x.to_str().unwrap().to_string())
-        .collect();
-    assert_eq!(expected, actual);
+    check_diff_args(&prog.into_vscmd(), dir.path());
 }
 
 /// test diff (with remove, with rename)
@@ -252,20 +319,7 @@ fn test_cmd_diff_rn() {
     assert!(fs::metadata(&second_cfg_bsl).is_err());
 
     // check args
-    let vscmd = prog.into_vscmd().into_iner();
-    let mut expected: Vec<String> = vec![];
-    expected.extend(COMMON_CODE_ARGS.into_iter().map(|x| x.to_string()));
-    expected.extend_from_slice(&[
-        CODE_CMD_DIFF.to_string(),
-        base_cfg_bsl.into_os_string().into_string().unwrap(),
-        second_cfg_bsl.into_os_string().into_string().unwrap(),
-    ]);
-
-    let actual: Vec<String> = vscmd
-        .get_args()
-        .map(|x| x.to_str().unwrap().to_string())
-        .collect();
-    assert_eq!(expected, actual);
+    check_diff_args(&prog.into_vscmd(), dir.path());
 }
 
 /// test merge (no remove, no rename, no from_second)
@@ -301,31 +355,21 @@ fn test_cmd_merge() {
     assert!(fs::metadata(&old_vendor_cfg).is_ok());
     assert!(fs::metadata(&merged).is_ok());
 
-    // check bsl files exists
-    assert!(fs::metadata(&base_cfg_bsl).is_ok());
-    assert!(fs::metadata(&second_cfg_bsl).is_ok());
-    assert!(fs::metadata(&old_vendor_bsl).is_ok());
-
-    // check merged_bsl not exist
+    // check bsl files not exists in platform's dir
+    assert!(fs::metadata(&base_cfg_bsl).is_err());
+    assert!(fs::metadata(&second_cfg_bsl).is_err());
+    assert!(fs::metadata(&old_vendor_bsl).is_err());
     assert!(fs::metadata(&merged_bsl).is_err());
 
     // check args
-    let vscmd = prog.into_vscmd().into_iner();
-    let mut expected: Vec<String> = vec![];
-    expected.extend(COMMON_CODE_ARGS.into_iter().map(|x| x.to_string()));
-    expected.extend_from_slice(&[
-        CODE_CMD_MERGE.to_string(),
-        base_cfg_bsl.into_os_string().into_string().unwrap(),
-        second_cfg_bsl.into_os_string().into_string().unwrap(),
-        old_vendor_bsl.into_os_string().into_string().unwrap(),
-        merged_bsl.into_os_string().into_string().unwrap(),
-    ]);
-
-    let actual: Vec<String> = vscmd
-        .get_args()
-        .map(|x| x.to_str().unwrap().to_string())
-        .collect();
-    assert_eq!(expected, actual);
+    let vscmd = prog.into_vscmd();
+    let staged = check_merge_args(&vscmd, dir.path());
+
+    // check staged content
+    assert_eq!(staged_content(&vscmd, &staged[0]), "base_cfg");
+    assert_eq!(staged_content(&vscmd, &staged[1]), "second_cfg");
+    assert_eq!(staged_content(&vscmd, &staged[2]), "old_vendor_cfg");
+    assert_eq!(staged_content(&vscmd, &staged[3]), "base_cfg");
 
     // check merged (default) content
     assert_eq!(
@@ -342,9 +386,6 @@ fn test_cmd_merge_s() {
     let second_cfg = dir.path().join("second_cfg.txt");
     let old_vendor_cfg = dir.path().join("old_vendor_cfg.txt");
     let merged = dir.path().join("merged.txt");
-    let base_cfg_bsl = base_cfg.with_extension(EXTENSION_BSL);
-    let second_cfg_bsl = second_cfg.with_extension(EXTENSION_BSL);
-    let old_vendor_bsl = old_vendor_cfg.with_extension(EXTENSION_BSL);
     let merged_bsl = merged.with_extension(EXTENSION_BSL);
 
     // create prog instance
@@ -367,31 +408,13 @@ fn test_cmd_merge_s() {
     assert!(fs::metadata(&old_vendor_cfg).is_ok());
     assert!(fs::metadata(&merged).is_ok());
 
-    // check bsl files exists
-    assert!(fs::metadata(&base_cfg_bsl).is_ok());
-    assert!(fs::metadata(&second_cfg_bsl).is_ok());
-    assert!(fs::metadata(&old_vendor_bsl).is_ok());
-
     // check merged_bsl not exist
     assert!(fs::metadata(&merged_bsl).is_err());
 
     // check args
-    let vscmd = prog.into_vscmd().into_iner();
-    let mut expected: Vec<String> = vec![];
-    expected.extend(COMMON_CODE_ARGS.into_iter().map(|x| x.to_string()));
-    expected.extend_from_slice(&[
-        CODE_CMD_MERGE.to_string(),
-        base_cfg_bsl.into_os_string().into_string().unwrap(),
-        second_cfg_bsl.into_os_string().into_string().unwrap(),
-        old_vendor_bsl.into_os_string().into_string().unwrap(),
-        merged_bsl.into_os_string().into_string().unwrap(),
-    ]);
-
-    let actual: Vec<String> = vscmd
-        .get_args()
-        .map(|x| x.to_str().unwrap().to_string())
-        .collect();
-    assert_eq!(expected, actual);
+    let vscmd = prog.into_vscmd();
+    let staged = check_merge_args(&vscmd, dir.path());
+    assert_eq!(staged_content(&vscmd, &staged[3]), "second_cfg");
 
     // check merged (default) content
     assert_eq!(
@@ -440,22 +463,7 @@ fn test_cmd_merge_r() {
     assert!(fs::metadata(&merged_bsl).is_err());
 
     // check args
-    let vscmd = prog.into_vscmd().into_iner();
-    let mut expected: Vec<String> = vec![];
-    expected.extend(COMMON_CODE_ARGS.into_iter().map(|x| x.to_string()));
-    expected.extend_from_slice(&[
-        CODE_CMD_MERGE.to_string(),
-        base_cfg_bsl.into_os_string().into_string().unwrap(),
-        second_cfg_bsl.into_os_string().into_string().unwrap(),
-        old_vendor_bsl.into_os_string().into_string().unwrap(),
-        merged_bsl.into_os_string().into_string().unwrap(),
-    ]);
-
-    let actual: Vec<String> = vscmd
-        .get_args()
-        .map(|x| x.to_str().unwrap().to_string())
-        .collect();
-    assert_eq!(expected, actual);
+    check_merge_args(&prog.into_vscmd(), dir.path());
 
     // check merged (default) content
     assert_eq!(
@@ -488,44 +496,27 @@ fn test_cmd_merge_n() {
         true,
     );
 
+    let base_content = fs::read_to_string(&base_cfg).unwrap();
+
     // check status code
     assert_eq!(prog.run().unwrap(), 0);
 
-    // check original files bot exists
-    assert!(fs::metadata(&base_cfg).is_err());
-    assert!(fs::metadata(&second_cfg).is_err());
-    assert!(fs::metadata(&old_vendor_cfg).is_err());
+    // check original files moved back
+    assert!(fs::metadata(&base_cfg).is_ok());
+    assert!(fs::metadata(&second_cfg).is_ok());
+    assert!(fs::metadata(&old_vendor_cfg).is_ok());
 
     // check merged exist
     assert!(fs::metadata(&merged).is_ok());
 
-    // check bsl files exists
-    assert!(fs::metadata(&base_cfg_bsl).is_ok());
-    assert!(fs::metadata(&second_cfg_bsl).is_ok());
-    assert!(fs::metadata(&old_vendor_bsl).is_ok());
-
-    // check merged_bsl not exist
+    // check bsl files not exists
+    assert!(fs::metadata(&base_cfg_bsl).is_err());
+    assert!(fs::metadata(&second_cfg_bsl).is_err());
+    assert!(fs::metadata(&old_vendor_bsl).is_err());
     assert!(fs::metadata(&merged_bsl).is_err());
 
-    let base_content = fs::read_to_string(&base_cfg_bsl).unwrap();
-
     // check args
-    let vscmd = prog.into_vscmd().into_iner();
-    let mut expected: Vec<String> = vec![];
-    expected.extend(COMMON_CODE_ARGS.into_iter().map(|x| x.to_string()));
-    expected.extend_from_slice(&[
-        CODE_CMD_MERGE.to_string(),
-        base_cfg_bsl.into_os_string().into_string().unwrap(),
-        second_cfg_bsl.into_os_string().into_string().unwrap(),
-        old_vendor_bsl.into_os_string().into_string().unwrap(),
-        merged_bsl.into_os_string().into_string().unwrap(),
-    ]);
-
-    let actual: Vec<String> = vscmd
-        .get_args()
-        .map(|x| x.to_str().unwrap().to_string())
-        .collect();
-    assert_eq!(expected, actual);
+    check_merge_args(&prog.into_vscmd(), dir.path());
 
     // check merged (default) content
     assert_eq!(fs::read_to_string(&merged).unwrap(), base_content)
@@ -575,23 +566,262 @@ fn test_cmd_merge_rn() {
     assert!(fs::metadata(&merged_bsl).is_err());
 
     // check args
-    let vscmd = prog.into_vscmd().into_iner();
-    let mut expected: Vec<String> = vec![];
-    expected.extend(COMMON_CODE_ARGS.into_iter().map(|x| x.to_string()));
-    expected.extend_from_slice(&[
-        CODE_CMD_MERGE.to_string(),
-        base_cfg_bsl.into_os_string().into_string().unwrap(),
-        second_cfg_bsl.into_os_string().into_string().unwrap(),
-        old_vendor_bsl.into_os_string().into_string().unwrap(),
-        merged_bsl.into_os_string().into_string().unwrap(),
-    ]);
-
-    let actual: Vec<String> = vscmd
-        .get_args()
-        .map(|x| x.to_str().unwrap().to_string())
-        .collect();
-    assert_eq!(expected, actual);
+    check_merge_args(&prog.into_vscmd(), dir.path());
 
     // check merged (default) content
     assert_eq!(fs::read_to_string(&merged).unwrap(), base_content)
 }
+
+/// test merge args order for non VSCode backends
+#[test]
+fn test_cmd_merge_backends() {
+    let dir = tempdir().expect("tempdir");
+    let base_cfg = dir.path().join("base_cfg.txt");
+    let second_cfg = dir.path().join("second_cfg.txt");
+    let old_vendor_cfg = dir.path().join("old_vendor_cfg.txt");
+    let merged = dir.path().join("merged.txt");
+
+    let cases: [(Backend, Vec<&str>); 3] = [
+        (
+            Backend::Meld,
+            vec![
+                "base_cfg",
+                "old_vendor_cfg",
+                "second_cfg",
+                OUTPUT_ARG,
+                "merged",
+            ],
+        ),
+        (
+            Backend::Kdiff3,
+            vec![
+                "old_vendor_cfg",
+                "base_cfg",
+                "second_cfg",
+                OUTPUT_ARG,
+                "merged",
+            ],
+        ),
+        (
+            Backend::P4merge,
+            vec![
+                "-C",
+                "utf8-bom",
+                "old_vendor_cfg",
+                "base_cfg",
+                "second_cfg",
+                "merged",
+            ],
+        ),
+    ];
+
+    for (backend, expected) in cases {
+        let mut prog = prepare_merge(
+            &base_cfg,
+            &second_cfg,
+            &old_vendor_cfg,
+            &merged,
+            false,
+            true,
+            false,
+        );
+        prog.backend = backend;
+
+        // check status code
+        assert_eq!(prog.run().unwrap(), 0);
+
+        // check args (staged file stems)
+        let vscmd = prog.into_vscmd().into_iner();
+        let actual: Vec<&str> = vscmd
+            .get_args()
+            .map(|x| match Path::new(x).file_stem() {
+                Some(stem) if Path::new(x).is_absolute() => stem.to_str().unwrap(),
+                _ => x.to_str().unwrap(),
+            })
+            .collect();
+        assert_eq!(expected, actual, "{backend:?}");
+    }
+}
+
+/// test staged files named by titles
+#[test]
+fn test_cmd_merge_titles() {
+    let dir = tempdir().expect("tempdir");
+    let base_cfg = dir.path().join("base_cfg.txt");
+    let second_cfg = dir.path().join("second_cfg.txt");
+    let old_vendor_cfg = dir.path().join("old_vendor_cfg.txt");
+    let merged = dir.path().join("merged.txt");
+
+    let mut prog = prepare_merge(
+        &base_cfg,
+        &second_cfg,
+        &old_vendor_cfg,
+        &merged,
+        false,
+        false,
+        false,
+    );
+    if let Some(Action::Merge {
+        base_title,
+        second_title,
+        old_vendor_title,
+        ..
+    }) = prog.action.as_mut()
+    {
+        *base_title = Some("Основная конфигурация".to_string());
+        *second_title = Some("Vendor: 1.0.2".to_string());
+        *old_vendor_title = Some("Vendor: 1.0.2".to_string());
+    }
+
+    assert_eq!(prog.run().unwrap(), 0);
+    assert_eq!(fs::read_to_string(&merged).unwrap(), "base_cfg");
+
+    let vscmd = prog.into_vscmd();
+    let args: Vec<OsString> = vscmd.inner.get_args().map(OsStr::to_owned).collect();
+    let workspace = workspace_of(&args);
+    let base_bsl = workspace.join("Основная конфигурация.bsl");
+    let second_bsl = workspace.join("Vendor_ 1.0.2.bsl");
+    let old_vendor_bsl = workspace.join("Vendor_ 1.0.2 (2).bsl");
+    let merged_bsl = workspace.join("merged.bsl");
+    assert_eq!(staged_content(&vscmd, &base_bsl), "base_cfg");
+    assert_eq!(staged_content(&vscmd, &second_bsl), "second_cfg");
+    assert_eq!(staged_content(&vscmd, &old_vendor_bsl), "old_vendor_cfg");
+
+    assert_eq!(
+        &args[COMMON_CODE_ARGS.len() + 2..],
+        [&base_bsl, &second_bsl, &old_vendor_bsl, &merged_bsl].map(|p| p.as_os_str())
+    );
+}
+
+/// test VSCode settings in staging workspace
+#[test]
+fn test_workspace_settings() {
+    let dir = tempdir().expect("tempdir");
+    let workspace = Workspace::new(Some(&dir.path().join("staging")), "bsl", Some("bsl")).unwrap();
+    let path = workspace.path().to_path_buf();
+    assert!(path.starts_with(dir.path().join("staging")));
+
+    let settings = fs::read_to_string(path.join(".vscode").join("settings.json")).unwrap();
+    assert!(settings.contains(r#""*.bsl": "bsl""#));
+
+    // unique names
+    let first = workspace.file_path(OsStr::new("Module"), "bsl");
+    assert_eq!(first, path.join("Module.bsl"));
+    fs::write(&first, "").unwrap();
+    let second = workspace.file_path(OsStr::new("Module"), "bsl");
+    assert_eq!(second, path.join("Module (2).bsl"));
+
+    workspace.close().unwrap();
+    assert!(fs::metadata(&path).is_err());
+
+    // known to editor
+    let workspace = Workspace::new(None, "xml", None).unwrap();
+    let settings =
+        fs::read_to_string(workspace.path().join(".vscode").join("settings.json")).unwrap();
+    assert_eq!(settings, "{\n    \"files.encoding\": \"utf8bom\"\n}\n");
+}
+
+/// editor that appends a line to merge result (last arg)
+struct EditingComand {
+    inner: process::Command,
+}
+
+impl WrappedCommand for EditingComand {
+    fn args<I, S>(&mut self, args: I) -> &mut Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<OsStr>,
+    {
+        self.inner.args(args);
+        self
+    }
+
+    fn status(&mut self) -> io::Result<ExitStatus> {
+        #[cfg(unix)]
+        use std::os::unix::process::ExitStatusExt;
+        #[cfg(windows)]
+        use std::os::windows::process::ExitStatusExt;
+        let merged = self.inner.get_args().last().unwrap();
+        let mut content = fs::read(merged)?;
+        content.extend_from_slice(b"\nedited");
+        fs::write(merged, content)?;
+        Ok(ExitStatus::from_raw(0))
+    }
+
+    fn get_program(&self) -> &OsStr {
+        self.inner.get_program()
+    }
+}
+
+/// test unchanged merge result detection
+#[test]
+fn test_cmd_merge_unchanged() {
+    let dir = tempdir().expect("tempdir");
+    let base_cfg = dir.path().join("base_cfg.txt");
+    let second_cfg = dir.path().join("second_cfg.txt");
+    let old_vendor_cfg = dir.path().join("old_vendor_cfg.txt");
+    let merged = dir.path().join("merged.txt");
+    let prepare = |code| {
+        let mut prog = prepare_merge(
+            &base_cfg,
+            &second_cfg,
+            &old_vendor_cfg,
+            &merged,
+            false,
+            false,
+            false,
+        );
+        prog.unchanged_exit_code = code;
+        prog
+    };
+
+    // not detected by default
+    assert_eq!(prepare(None).run().unwrap(), 0);
+    assert_eq!(prepare(Some(EXIT_UNCHANGED)).run().unwrap(), EXIT_UNCHANGED);
+    assert_eq!(prepare(Some(10)).run().unwrap(), 10);
+    // result is written anyway
+    assert_eq!(fs::read_to_string(&merged).unwrap(), "base_cfg");
+
+    // changed in editor
+    let prog = prepare(Some(EXIT_UNCHANGED));
+    let mut prog = Program::new_test(
+        prog.action.unwrap(),
+        EditingComand {
+            inner: process::Command::new("echo"),
+        },
+        false,
+        false,
+    );
+    prog.unchanged_exit_code = Some(EXIT_UNCHANGED);
+    assert_eq!(prog.run().unwrap(), 0);
+    assert_eq!(fs::read_to_string(&merged).unwrap(), "base_cfg\nedited");
+}
+
+/// program built with options for embedding, action is done once
+#[test]
+fn test_program_builder() {
+    let dir = tempdir().expect("tempdir");
+    let base_cfg = dir.path().join("base_cfg.txt");
+    let second_cfg = dir.path().join("second_cfg.txt");
+    let action = prepare_diff(&base_cfg, &second_cfg, false, false)
+        .action
+        .unwrap();
+
+    let mut prog = Program::builder(action, TestComand::new())
+        .backend(Backend::Meld)
+        .extension("os")
+        .staging_dir(Some(dir.path().join("staging")))
+        .build();
+    assert_eq!(prog.run().unwrap(), 0);
+    assert!(prog.run().is_err());
+
+    let vscmd = prog.into_vscmd();
+    let names: Vec<&OsStr> = vscmd
+        .files
+        .iter()
+        .filter_map(|(p, _)| p.file_name())
+        .collect();
+    assert_eq!(names, ["base_cfg.os", "second_cfg.os"]);
+    let staged = vscmd.files[0].0.parent().unwrap();
+    assert!(staged.starts_with(dir.path().join("staging")));
+}
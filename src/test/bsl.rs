@@ -0,0 +1,191 @@
+use std::fs;
+
+use super::prepare_merge;
+use crate::bsl::*;
+use crate::merge3::*;
+use tempfile::tempdir;
+
+const LABELS: Labels = Labels {
+    ours: "base",
+    ancestor: "old",
+    theirs: "second",
+};
+
+const MODULE: &str = "Перем мКэш;
+
+#Область ПрограммныйИнтерфейс
+
+// Возвращает значение.
+&НаСервере
+Функция Значение(Параметр) Экспорт
+    Возврат Параметр;
+КонецФункции
+
+#Region Internal
+&AtClient
+Async Procedure Refresh()
+EndProcedure
+#EndRegion
+
+#КонецОбласти
+
+Процедура ПриОткрытии (Отказ)
+    // Процедура Вложенная()
+КонецПроцедуры";
+
+/// methods from parsed module
+fn methods<'a>(items: &'a [Item<'a>]) -> Vec<&'a Method<'a>> {
+    items
+        .iter()
+        .filter_map(|item| match item {
+            Item::Method(method) => Some(method),
+            Item::Text(_) => None,
+        })
+        .collect()
+}
+
+#[test]
+fn test_parse() {
+    let items = parse(MODULE).unwrap();
+    let methods = methods(&items);
+
+    let names: Vec<&str> = methods.iter().map(|m| m.name).collect();
+    assert_eq!(names, ["Значение", "Refresh", "ПриОткрытии"]);
+    assert_eq!(methods[0].directives, ["&НаСервере"]);
+    assert_eq!(methods[0].region, ["ПрограммныйИнтерфейс"]);
+    assert!(methods[0]
+        .text
+        .starts_with("// Возвращает значение.\n&НаСервере\n"));
+    assert!(methods[0].text.ends_with("КонецФункции\n"));
+    assert_eq!(methods[1].directives, ["&AtClient"]);
+    assert_eq!(methods[1].region, ["ПрограммныйИнтерфейс", "Internal"]);
+    assert!(methods[2].region.is_empty());
+    assert_eq!(methods[0].key(), "значение");
+
+    // nothing is lost
+    let text: String = items
+        .iter()
+        .map(|item| match item {
+            Item::Text(text) => text.as_str(),
+            Item::Method(method) => method.text.as_str(),
+        })
+        .collect();
+    assert_eq!(text, MODULE);
+}
+
+#[test]
+fn test_parse_errors() {
+    assert!(parse("Процедура А()\n").is_err());
+    assert!(parse("#Область А\n").is_err());
+    assert!(parse("#КонецОбласти\n").is_err());
+    // not a method
+    assert!(methods(&parse("Функция = 1;\nПроцедураА();\n").unwrap()).is_empty());
+}
+
+const OLD: &str = "Процедура А()
+    А = 1;
+КонецПроцедуры
+
+Процедура Б()
+    Б = 1;
+КонецПроцедуры
+
+Процедура В()
+    В = 1;
+КонецПроцедуры
+";
+
+/// vendor reordered methods, we changed one of them
+#[test]
+fn test_merge_structural_reorder() {
+    let ours = OLD.replace("А = 1", "А = 2");
+    let theirs = "Процедура В()
+    В = 1;
+КонецПроцедуры
+
+Процедура Б()
+    Б = 2;
+КонецПроцедуры
+
+Процедура А()
+    А = 1;
+КонецПроцедуры
+";
+    let expected = theirs.replace("А = 1", "А = 2");
+    assert_eq!(
+        merge_structural(OLD, &ours, theirs, LABELS).unwrap(),
+        Merged::Clean(expected)
+    );
+}
+
+/// conflict is inside method only
+#[test]
+fn test_merge_structural_conflict() {
+    let ours = OLD.replace("Б = 1", "Б = 2");
+    let theirs = OLD.replace("Б = 1", "Б = 3");
+    let expected = OLD.replace(
+        "    Б = 1;\n",
+        "<<<<<<< base\n    Б = 2;\n||||||| old\n    Б = 1;\n=======\n    Б = 3;\n>>>>>>> second\n",
+    );
+    assert_eq!(
+        merge_structural(OLD, &ours, &theirs, LABELS).unwrap(),
+        Merged::Conflicts {
+            text: expected,
+            count: 1
+        }
+    );
+}
+
+/// method changed by us and deleted by vendor is a conflict, unchanged one is deleted
+#[test]
+fn test_merge_structural_deleted() {
+    let ours = OLD.replace("Б = 1", "Б = 2");
+    let theirs = OLD.replace("\nПроцедура Б()\n    Б = 1;\nКонецПроцедуры\n", "");
+    let Merged::Conflicts { text, count } = merge_structural(OLD, &ours, &theirs, LABELS).unwrap()
+    else {
+        panic!("conflict expected");
+    };
+    assert_eq!(count, 1);
+    let conflict = "<<<<<<< base\nПроцедура Б()\n    Б = 2;\nКонецПроцедуры\n\n||||||| old\n";
+    assert!(text.contains(conflict));
+    assert!(text.ends_with("=======\n>>>>>>> second\nПроцедура В()\n    В = 1;\nКонецПроцедуры\n"));
+
+    assert_eq!(
+        merge_structural(OLD, OLD, &theirs, LABELS).unwrap(),
+        Merged::Clean(theirs)
+    );
+}
+
+/// structural merge of modules from platform
+#[test]
+fn test_cmd_merge_structural() {
+    let dir = tempdir().expect("tempdir");
+    let base_cfg = dir.path().join("base_cfg.txt");
+    let second_cfg = dir.path().join("second_cfg.txt");
+    let old_vendor_cfg = dir.path().join("old_vendor_cfg.txt");
+    let merged = dir.path().join("merged.txt");
+
+    let mut prog = prepare_merge(
+        &base_cfg,
+        &second_cfg,
+        &old_vendor_cfg,
+        &merged,
+        false,
+        false,
+        false,
+    );
+    prog.structural = true;
+    let ours = OLD.replace("А = 1", "А = 2");
+    let theirs = OLD.replace("Процедура А()\n    А = 1;\nКонецПроцедуры\n\n", "")
+        + "\nПроцедура А()\n    А = 1;\nКонецПроцедуры\n";
+    fs::write(&old_vendor_cfg, OLD).unwrap();
+    fs::write(&base_cfg, &ours).unwrap();
+    fs::write(&second_cfg, &theirs).unwrap();
+
+    assert_eq!(prog.run().unwrap(), 0);
+    assert_eq!(
+        fs::read_to_string(&merged).unwrap(),
+        theirs.replace("А = 1", "А = 2")
+    );
+    assert!(prog.into_vscmd().files.is_empty());
+}
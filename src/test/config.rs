@@ -0,0 +1,175 @@
+use std::fs;
+
+use clap::Parser;
+use tempfile::tempdir;
+
+use crate::config::*;
+use crate::editor::Backend;
+use crate::test::TestComand;
+use crate::{Cli, Program, EXIT_UNCHANGED, EXTENSION_BSL};
+
+#[test]
+fn test_config_parse() {
+    let config: Config = toml::from_str(
+        r#"
+        backend = "p4merge"
+        editor = 'C:\Program Files\Perforce\p4merge.exe'
+        editor-args = ["--disable-extensions"]
+        extension = "txt"
+        merge-seed = "second"
+        remove-files = true
+        rename-files = false
+        "#,
+    )
+    .unwrap();
+
+    assert_eq!(config.backend, Some(Backend::P4merge));
+    assert_eq!(
+        config.editor.unwrap().to_str().unwrap(),
+        r"C:\Program Files\Perforce\p4merge.exe"
+    );
+    assert_eq!(config.editor_args.unwrap(), ["--disable-extensions"]);
+    assert_eq!(config.extension.unwrap(), "txt");
+    assert_eq!(config.merge_seed, Some(MergeSeed::Second));
+    assert_eq!(config.remove_files, Some(true));
+    assert_eq!(config.rename_files, Some(false));
+}
+
+#[test]
+fn test_config_unknown_key() {
+    assert!(toml::from_str::<Config>("remove_files = true").is_err());
+}
+
+#[test]
+fn test_config_merge() {
+    let user = Config {
+        backend: Some(Backend::Meld),
+        extension: Some("txt".to_string()),
+        remove_files: Some(true),
+        ..Default::default()
+    };
+    let project = Config {
+        extension: Some("bsl".to_string()),
+        remove_files: Some(false),
+        ..Default::default()
+    };
+
+    let config = user.merge(project);
+    assert_eq!(config.backend, Some(Backend::Meld));
+    assert_eq!(config.extension.as_deref(), Some("bsl"));
+    assert_eq!(config.remove_files, Some(false));
+    assert_eq!(config.rename_files, None);
+}
+
+#[test]
+fn test_project_config_path() {
+    let dir = tempdir().expect("tempdir");
+    let nested = dir.path().join("src").join("CommonModules");
+    fs::create_dir_all(&nested).unwrap();
+    assert_eq!(project_config_path(&nested), None);
+
+    let config_path = dir.path().join(PROJECT_CONFIG_FILE);
+    fs::write(&config_path, "").unwrap();
+    assert_eq!(project_config_path(&nested), Some(config_path.clone()));
+
+    assert_eq!(Config::from_file(&config_path).unwrap(), Config::default());
+}
+
+/// CLI flags take precedence over config
+#[test]
+fn test_config_cli_precedence() {
+    let config = Config {
+        backend: Some(Backend::Meld),
+        merge_seed: Some(MergeSeed::Second),
+        rename_files: Some(true),
+        ..Default::default()
+    };
+
+    let cli = Cli::parse_from(["adapter", "-b", "kdiff3", "-r", "diff", "a.txt", "b.txt"]);
+    let prog = Program::new(cli, config.clone(), TestComand::new());
+    assert_eq!(prog.backend, Backend::Kdiff3);
+    assert!(prog.remove_files);
+    assert!(prog.rename_files);
+    assert_eq!(prog.merge_seed, MergeSeed::Second);
+    assert_eq!(prog.extension, EXTENSION_BSL);
+
+    let cli = Cli::parse_from(["adapter", "diff", "a.txt", "b.txt"]);
+    let prog = Program::new(cli, config, TestComand::new());
+    assert_eq!(prog.backend, Backend::Meld);
+    assert!(!prog.remove_files);
+}
+
+/// flags enabled in config are disabled by `--no-` flags, the last flag wins
+#[test]
+fn test_config_cli_disables() {
+    let config = Config {
+        remove_files: Some(true),
+        rename_files: Some(true),
+        reopen_on_conflicts: Some(true),
+        auto_merge: Some(true),
+        structural: Some(true),
+        detect_unchanged: Some(true),
+        ..Default::default()
+    };
+
+    let cli = Cli::parse_from(["adapter", "diff", "a.txt", "b.txt"]);
+    let prog = Program::new(cli, config.clone(), TestComand::new());
+    assert!(prog.remove_files && prog.rename_files && prog.reopen_on_conflicts);
+    assert!(prog.auto_merge && prog.structural);
+    assert_eq!(prog.unchanged_exit_code, Some(EXIT_UNCHANGED));
+
+    let cli = Cli::parse_from([
+        "adapter",
+        "--no-remove-files",
+        "--no-rename-files",
+        "--no-reopen-on-conflicts",
+        "--no-auto-merge",
+        "--no-structural",
+        "--no-detect-unchanged",
+        "diff",
+        "a.txt",
+        "b.txt",
+    ]);
+    let prog = Program::new(cli, config.clone(), TestComand::new());
+    assert!(!prog.remove_files && !prog.rename_files && !prog.reopen_on_conflicts);
+    assert!(!prog.auto_merge && !prog.structural);
+    assert_eq!(prog.unchanged_exit_code, None);
+
+    let cli = Cli::parse_from([
+        "adapter",
+        "--auto-merge",
+        "--no-auto-merge",
+        "-r",
+        "diff",
+        "a.txt",
+        "b.txt",
+    ]);
+    let prog = Program::new(cli, Config::default(), TestComand::new());
+    assert!(!prog.auto_merge);
+    assert!(prog.remove_files);
+}
+
+/// unchanged result is detected only if enabled, exit code from CLI or config
+#[test]
+fn test_config_unchanged_exit_code() {
+    let config = Config {
+        unchanged_exit_code: Some(5),
+        ..Default::default()
+    };
+
+    let cli = Cli::parse_from(["adapter", "diff", "a.txt", "b.txt"]);
+    let prog = Program::new(cli, config.clone(), TestComand::new());
+    assert_eq!(prog.unchanged_exit_code, None);
+
+    let cli = Cli::parse_from(["adapter", "--detect-unchanged", "diff", "a.txt", "b.txt"]);
+    let prog = Program::new(cli, config.clone(), TestComand::new());
+    assert_eq!(prog.unchanged_exit_code, Some(5));
+
+    let config = Config {
+        detect_unchanged: Some(true),
+        ..Default::default()
+    };
+    let cli = Cli::parse_from(["adapter", "diff", "a.txt", "b.txt"]);
+    let prog = Program::new(cli, config, TestComand::new());
+    assert_eq!(prog.unchanged_exit_code, Some(EXIT_UNCHANGED));
+}
@@ -0,0 +1,74 @@
+use std::fs;
+
+use super::prepare_merge;
+use crate::conflicts::find_markers;
+use crate::*;
+use tempfile::tempdir;
+
+const CONFLICT: &str = "\u{feff}Процедура А()
+<<<<<<< base
+    Сообщить(1);
+||||||| old vendor
+    Сообщить(0);
+=======
+    Сообщить(2);
+>>>>>>> second
+КонецПроцедуры
+";
+
+#[test]
+fn test_find_markers() {
+    assert_eq!(find_markers(CONFLICT), vec![2, 4, 6, 8]);
+    assert_eq!(find_markers("<<<<<<<\n=======\n>>>>>>>"), vec![1, 2, 3]);
+    // not markers
+    assert!(find_markers("// <<<<<<< base\n========\n<<<<<<<<\nА = Б;").is_empty());
+    assert!(find_markers("").is_empty());
+}
+
+/// merge result with markers is written, but exit code is `EXIT_CONFLICTS`
+#[test]
+fn test_cmd_merge_conflicts() {
+    let dir = tempdir().expect("tempdir");
+    let base_cfg = dir.path().join("base_cfg.txt");
+    let merged = dir.path().join("merged.txt");
+
+    for reopen in [false, true] {
+        let mut prog = prepare_merge(
+            &base_cfg,
+            &dir.path().join("second_cfg.txt"),
+            &dir.path().join("old_vendor_cfg.txt"),
+            &merged,
+            false,
+            false,
+            false,
+        );
+        prog.reopen_on_conflicts = reopen;
+        fs::write(&base_cfg, CONFLICT).unwrap();
+
+        assert_eq!(prog.run().unwrap(), EXIT_CONFLICTS);
+        assert_eq!(fs::read_to_string(&merged).unwrap(), CONFLICT);
+
+        // 4 files on each launch
+        let launches = if reopen { 1 + MAX_REOPEN } else { 1 };
+        assert_eq!(prog.into_vscmd().files.len(), 4 * launches);
+    }
+}
+
+/// editor is not reopened if there are no markers
+#[test]
+fn test_cmd_merge_no_conflicts() {
+    let dir = tempdir().expect("tempdir");
+    let mut prog = prepare_merge(
+        &dir.path().join("base_cfg.txt"),
+        &dir.path().join("second_cfg.txt"),
+        &dir.path().join("old_vendor_cfg.txt"),
+        &dir.path().join("merged.txt"),
+        false,
+        false,
+        false,
+    );
+    prog.reopen_on_conflicts = true;
+
+    assert_eq!(prog.run().unwrap(), 0);
+    assert_eq!(prog.into_vscmd().files.len(), 4);
+}
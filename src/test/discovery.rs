@@ -0,0 +1,99 @@
+use std::{fs, path::Path};
+
+use tempfile::tempdir;
+
+use crate::discovery::*;
+
+/// create executable file
+fn touch_exe(path: &Path) {
+    fs::write(path, "").unwrap();
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(path, fs::Permissions::from_mode(0o755)).unwrap();
+    }
+}
+
+fn exe_name(name: &str) -> String {
+    format!("{name}{}", std::env::consts::EXE_SUFFIX)
+}
+
+#[test]
+fn test_find_in_path_by_candidates() {
+    let dir = tempdir().expect("tempdir");
+    let codium = dir.path().join(exe_name("codium"));
+    touch_exe(&codium);
+
+    let search = EditorSearch {
+        names: vec!["code", "codium"],
+        path_var: Some(dir.path().as_os_str().to_owned()),
+        ..Default::default()
+    };
+    assert_eq!(search.find().unwrap(), codium);
+}
+
+#[test]
+fn test_find_in_locations() {
+    let dir = tempdir().expect("tempdir");
+    let location = dir.path().join("Microsoft VS Code").join(exe_name("code"));
+    fs::create_dir_all(location.parent().unwrap()).unwrap();
+    touch_exe(&location);
+
+    let search = EditorSearch {
+        names: vec!["code"],
+        path_var: Some(dir.path().join("empty").into_os_string()),
+        locations: vec![dir.path().join("missing"), location.clone()],
+        ..Default::default()
+    };
+    assert_eq!(search.find().unwrap(), location);
+}
+
+#[test]
+fn test_explicit_and_env() {
+    let dir = tempdir().expect("tempdir");
+    let portable = dir.path().join(exe_name("portable-code"));
+    let from_env = dir.path().join(exe_name("env-code"));
+    touch_exe(&portable);
+    touch_exe(&from_env);
+
+    let mut search = EditorSearch {
+        explicit: Some(portable.clone()),
+        env: Some(from_env.clone()),
+        ..Default::default()
+    };
+    assert_eq!(search.find().unwrap(), portable);
+
+    search.explicit = None;
+    assert_eq!(search.find().unwrap(), from_env);
+
+    // explicit path is not a fallback
+    search.explicit = Some(dir.path().join("missing"));
+    let err = search.find().unwrap_err();
+    assert_eq!(err.tried.len(), 1);
+    assert!(err.tried[0].starts_with("--editor "));
+}
+
+#[test]
+fn test_not_found_lists_tried() {
+    let dir = tempdir().expect("tempdir");
+    let location = dir.path().join("code");
+
+    let search = EditorSearch {
+        names: vec!["code", "code-insiders"],
+        path_var: Some(dir.path().as_os_str().to_owned()),
+        locations: vec![location.clone()],
+        ..Default::default()
+    };
+    let err = search.find().unwrap_err();
+    assert_eq!(
+        err.tried,
+        [
+            "code (PATH)".to_string(),
+            "code-insiders (PATH)".to_string(),
+            location.display().to_string()
+        ]
+    );
+    assert!(err
+        .to_string()
+        .starts_with("editor not found, tried:\n  code (PATH)"));
+}
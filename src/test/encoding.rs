@@ -0,0 +1,170 @@
+use std::fs;
+
+use clap::ValueEnum;
+use tempfile::tempdir;
+
+use crate::encoding::*;
+use crate::test::prepare_merge;
+use crate::EXTENSION_BSL;
+
+const TEXT: &str = "Процедура Тест()\r\n\tСообщить(\"Привет\");\r\nКонецПроцедуры\r\n";
+
+#[test]
+fn test_detect() {
+    assert_eq!(
+        detect(&encode(TEXT, TextEncoding::Utf8Bom)),
+        TextEncoding::Utf8Bom
+    );
+    assert_eq!(detect(TEXT.as_bytes()), TextEncoding::Utf8);
+    assert_eq!(
+        detect(&encode(TEXT, TextEncoding::Utf16le)),
+        TextEncoding::Utf16le
+    );
+    assert_eq!(
+        detect(&encode(TEXT, TextEncoding::Utf16be)),
+        TextEncoding::Utf16be
+    );
+    assert_eq!(
+        detect(&encode(TEXT, TextEncoding::Cp1251)),
+        TextEncoding::Cp1251
+    );
+    assert_eq!(detect(b""), TextEncoding::Utf8);
+
+    // UTF-16 without BOM
+    let le: Vec<u8> = "Procedure Test()"
+        .encode_utf16()
+        .flat_map(u16::to_le_bytes)
+        .collect();
+    assert_eq!(detect(&le), TextEncoding::Utf16le);
+    let be: Vec<u8> = "Procedure Test()"
+        .encode_utf16()
+        .flat_map(u16::to_be_bytes)
+        .collect();
+    assert_eq!(detect(&be), TextEncoding::Utf16be);
+
+    // Cyrillic UTF-16 without BOM has no zero bytes and is valid UTF-8
+    let module = "Процедура ОбработкаПроведения(Отказ, РежимПроведения)";
+    let le: Vec<u8> = module.encode_utf16().flat_map(u16::to_le_bytes).collect();
+    assert!(std::str::from_utf8(&le).is_ok());
+    assert_eq!(detect(&le), TextEncoding::Utf16le);
+    assert_eq!(decode(&le, detect(&le)), module);
+    let be: Vec<u8> = module.encode_utf16().flat_map(u16::to_be_bytes).collect();
+    assert_eq!(detect(&be), TextEncoding::Utf16be);
+}
+
+#[test]
+fn test_roundtrip() {
+    for encoding in TextEncoding::value_variants() {
+        let bytes = encode(TEXT, *encoding);
+        assert_eq!(decode(&bytes, detect(&bytes)), TEXT, "{encoding:?}");
+    }
+}
+
+#[test]
+fn test_to_editor() {
+    let (encoding, converted) = to_editor(&encode(TEXT, TextEncoding::Utf8Bom));
+    assert_eq!(encoding, TextEncoding::Utf8Bom);
+    assert!(converted.is_none());
+
+    let (encoding, converted) = to_editor(&encode(TEXT, TextEncoding::Cp1251));
+    assert_eq!(encoding, TextEncoding::Cp1251);
+    assert_eq!(converted.unwrap(), encode(TEXT, TextEncoding::Utf8Bom));
+}
+
+/// merge of cp1251 files: editor gets UTF-8, result written back in cp1251 or in `output_encoding`
+#[test]
+fn test_cmd_merge_cp1251() {
+    let dir = tempdir().expect("tempdir");
+    let base_cfg = dir.path().join("base_cfg.txt");
+    let second_cfg = dir.path().join("second_cfg.txt");
+    let old_vendor_cfg = dir.path().join("old_vendor_cfg.txt");
+    let merged = dir.path().join("merged.txt");
+
+    for (output_encoding, expected) in [
+        (None, TextEncoding::Cp1251),
+        (Some(TextEncoding::Utf8Bom), TextEncoding::Utf8Bom),
+    ] {
+        let mut prog = prepare_merge(
+            &base_cfg,
+            &second_cfg,
+            &old_vendor_cfg,
+            &merged,
+            false,
+            false,
+            false,
+        );
+        prog.output_encoding = output_encoding;
+        fs::write(&base_cfg, encode(TEXT, TextEncoding::Cp1251)).unwrap();
+
+        assert_eq!(prog.run().unwrap(), 0);
+
+        // editor copies are UTF-8
+        let vscmd = prog.into_vscmd();
+        let (_, base_bsl) = &vscmd.files[0];
+        assert_eq!(*base_bsl, encode(TEXT, TextEncoding::Utf8Bom));
+        let (merged_bsl, content) = &vscmd.files[3];
+        assert_eq!(merged_bsl.extension().unwrap(), EXTENSION_BSL);
+        assert_eq!(*content, encode(TEXT, TextEncoding::Utf8Bom));
+
+        // original is untouched
+        let base = fs::read(&base_cfg).unwrap();
+        assert_eq!(base, encode(TEXT, TextEncoding::Cp1251));
+
+        let merged_bytes = fs::read(&merged).unwrap();
+        assert_eq!(merged_bytes, encode(TEXT, expected));
+    }
+}
+
+/// sources with the staged extension are converted into separate copies, never in place
+/// (not even with `--rename-files`)
+#[test]
+fn test_cmd_merge_bsl_sources_untouched() {
+    let dir = tempdir().expect("tempdir");
+    let base_cfg = dir.path().join("base_cfg.bsl");
+    let second_cfg = dir.path().join("second_cfg.bsl");
+    let old_vendor_cfg = dir.path().join("old_vendor_cfg.bsl");
+    let merged = dir.path().join("merged.bsl");
+
+    for rename_files in [false, true] {
+        let mut prog = prepare_merge(
+            &base_cfg,
+            &second_cfg,
+            &old_vendor_cfg,
+            &merged,
+            false,
+            false,
+            rename_files,
+        );
+        fs::write(&base_cfg, encode(TEXT, TextEncoding::Cp1251)).unwrap();
+        fs::write(&second_cfg, encode(TEXT, TextEncoding::Utf16le)).unwrap();
+
+        assert_eq!(prog.run().unwrap(), 0);
+
+        let vscmd = prog.into_vscmd();
+        for (staged, content) in &vscmd.files[..2] {
+            assert_ne!(staged.parent(), Some(dir.path()));
+            assert_eq!(*content, encode(TEXT, TextEncoding::Utf8Bom));
+        }
+        assert_eq!(
+            fs::read(&base_cfg).unwrap(),
+            encode(TEXT, TextEncoding::Cp1251)
+        );
+        assert_eq!(
+            fs::read(&second_cfg).unwrap(),
+            encode(TEXT, TextEncoding::Utf16le)
+        );
+    }
+}
+
+#[test]
+fn test_read_text() {
+    let dir = tempdir().expect("tempdir");
+    let path = dir.path().join("Module.txt");
+    fs::write(&path, encode(TEXT, TextEncoding::Utf16le)).unwrap();
+    let (text, encoding) = crate::read_text(&path).unwrap();
+    assert_eq!(text, TEXT);
+    assert_eq!(encoding, TextEncoding::Utf16le);
+
+    let err = crate::read_text(&dir.path().join("missing.txt")).unwrap_err();
+    assert!(matches!(err, crate::Error::Read { .. }));
+}
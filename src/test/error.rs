@@ -0,0 +1,113 @@
+use std::{fs, io, path::PathBuf};
+
+use tempfile::tempdir;
+
+use super::{prepare_merge, TestComand};
+use crate::error::*;
+use crate::i18n::Lang;
+use crate::staging::Transaction;
+use crate::utils::write_back;
+use crate::*;
+
+#[test]
+fn test_lang_from_locale() {
+    assert_eq!(Lang::from_locale("ru_RU.UTF-8"), Some(Lang::Ru));
+    assert_eq!(Lang::from_locale("ru"), Some(Lang::Ru));
+    assert_eq!(Lang::from_locale("en_US"), Some(Lang::En));
+    assert_eq!(Lang::from_locale("C.UTF-8"), Some(Lang::En));
+    assert_eq!(Lang::from_locale("de_DE"), None);
+    assert_eq!(Lang::from_locale(""), None);
+}
+
+#[test]
+fn test_error_messages() {
+    let err = Error::Read {
+        path: PathBuf::from("base.txt"),
+        source: io::Error::from(io::ErrorKind::NotFound),
+    };
+    assert!(err.message(Lang::En).starts_with("can't read base.txt: "));
+    assert_eq!(
+        err.message(Lang::Ru),
+        "не удалось прочитать base.txt: файл не найден"
+    );
+
+    let err = Error::ProfileNotFound { name: "p4".into() };
+    assert_eq!(
+        err.message(Lang::Ru),
+        "профиль p4 не найден в файле настроек"
+    );
+    assert_eq!(err.message(Lang::En), "profile p4 not found in config");
+
+    // display is the english message, its template is the key of translation
+    let errors = [
+        Error::AlreadyRun,
+        Error::CurrentDir {
+            source: io::ErrorKind::NotFound.into(),
+        },
+        Error::Log {
+            dir: "logs".into(),
+            source: io::ErrorKind::PermissionDenied.into(),
+        },
+        Error::JournalMismatch {
+            path: "update.json".into(),
+        },
+    ];
+    for err in errors {
+        assert_eq!(err.to_string(), err.message(Lang::En));
+        assert_ne!(err.message(Lang::Ru), err.message(Lang::En));
+    }
+}
+
+/// errors carry the offending paths
+#[test]
+fn test_typed_errors() {
+    let dir = tempdir().expect("tempdir");
+    let base_cfg = dir.path().join("base_cfg.txt");
+    let merged = dir.path().join("merged.txt");
+    let mut prog = prepare_merge(
+        &base_cfg,
+        &dir.path().join("second_cfg.txt"),
+        &dir.path().join("old_vendor_cfg.txt"),
+        &merged,
+        false,
+        false,
+        false,
+    );
+    fs::remove_file(&base_cfg).unwrap();
+    let err = prog.run().unwrap_err();
+    assert!(matches!(err, Error::Read { path, .. } if path == base_cfg));
+
+    // staging dir is a file
+    let mut prog = Program::new_test(
+        prepare_merge(
+            &base_cfg,
+            &dir.path().join("second_cfg.txt"),
+            &dir.path().join("old_vendor_cfg.txt"),
+            &merged,
+            false,
+            false,
+            false,
+        )
+        .action
+        .unwrap(),
+        TestComand::new(),
+        false,
+        false,
+    );
+    prog.staging_dir = Some(base_cfg.clone());
+    let err = prog.run().unwrap_err();
+    assert!(matches!(err, Error::Workspace { dir, .. } if dir == base_cfg));
+
+    // result can't be written
+    let missing = dir.path().join("missing").join("merged.txt");
+    let err = write_back(&base_cfg, &missing, encoding::TextEncoding::Utf8).unwrap_err();
+    assert!(matches!(err, Error::WriteBack { path, .. } if path == missing));
+
+    // moved file can't be moved back
+    let staged = dir.path().join("staged.bsl");
+    let mut tx = Transaction::new();
+    tx.move_file(&base_cfg, &staged).unwrap();
+    fs::remove_file(&staged).unwrap();
+    let err = tx.rollback().unwrap_err();
+    assert!(matches!(err, Error::RenameBack { from, to, .. } if from == staged && to == base_cfg));
+}
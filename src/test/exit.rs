@@ -0,0 +1,169 @@
+use std::{
+    fs, io,
+    process::{self, ExitStatus},
+};
+
+#[cfg(unix)]
+use std::os::unix::process::ExitStatusExt;
+#[cfg(windows)]
+use std::os::windows::process::ExitStatusExt;
+
+use tempfile::tempdir;
+
+use super::{prepare_diff, staging::FailingComand, TestComand};
+use crate::discovery::EditorNotFound;
+use crate::exit::*;
+use crate::*;
+
+/// editor that exits with `status` without touching files
+pub(super) struct ExitingComand {
+    inner: process::Command,
+    status: ExitStatus,
+}
+
+impl ExitingComand {
+    /// editor exiting with raw `status` (see `ExitStatusExt::from_raw`)
+    pub(super) fn new(raw: u32) -> Self {
+        Self {
+            inner: process::Command::new("editor"),
+            status: ExitStatus::from_raw(raw as _),
+        }
+    }
+}
+
+impl WrappedCommand for ExitingComand {
+    fn args<I, S>(&mut self, args: I) -> &mut Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<OsStr>,
+    {
+        self.inner.args(args);
+        self
+    }
+
+    fn status(&mut self) -> io::Result<ExitStatus> {
+        Ok(self.status)
+    }
+
+    fn get_program(&self) -> &OsStr {
+        self.inner.get_program()
+    }
+}
+
+/// diff program with `vscmd` and files in `dir`
+fn diff_with<C: WrappedCommand>(dir: &Path, vscmd: C) -> Program<C> {
+    let prog = prepare_diff(
+        &dir.join("base_cfg.txt"),
+        &dir.join("second_cfg.txt"),
+        false,
+        false,
+    );
+    Program::new_test(prog.action.unwrap(), vscmd, false, false)
+}
+
+#[test]
+fn test_error_code() {
+    let err = Error::ProfileNotFound { name: "x".into() };
+    assert_eq!(err.exit_code(), EXIT_INVALID_ARGS);
+    assert_eq!(Error::Interrupted.exit_code(), EXIT_UNCHANGED);
+    let err = Error::from(EditorNotFound { tried: vec![] });
+    assert_eq!(err.exit_code(), EXIT_EDITOR_NOT_FOUND);
+    let err = Error::Launch {
+        program: "code".into(),
+        source: io::Error::from(io::ErrorKind::PermissionDenied),
+    };
+    assert_eq!(err.exit_code(), EXIT_EDITOR_NOT_FOUND);
+    let err = Error::Log {
+        dir: "logs".into(),
+        source: io::Error::other("disk full"),
+    };
+    assert_eq!(err.exit_code(), EXIT_IO_ERROR);
+}
+
+#[test]
+fn test_editor_code() {
+    assert_eq!(editor_code(ExitStatus::from_raw(0)), EXIT_SUCCESS);
+    // exit code 1
+    #[cfg(unix)]
+    assert_eq!(
+        editor_code(ExitStatus::from_raw(1 << 8)),
+        EXIT_EDITOR_FAILED
+    );
+    #[cfg(windows)]
+    assert_eq!(editor_code(ExitStatus::from_raw(1)), EXIT_EDITOR_FAILED);
+    // killed by SIGKILL
+    #[cfg(unix)]
+    assert_eq!(editor_code(ExitStatus::from_raw(9)), EXIT_EDITOR_FAILED);
+}
+
+/// editor failures are not reported as success
+#[test]
+fn test_cmd_exit_codes() {
+    let dir = tempdir().expect("tempdir");
+    let exiting = ExitingComand::new;
+
+    assert_eq!(
+        diff_with(dir.path(), exiting(0)).run().unwrap(),
+        EXIT_SUCCESS
+    );
+    #[cfg(unix)]
+    for raw in [1 << 8, 9] {
+        let code = diff_with(dir.path(), exiting(raw)).run().unwrap();
+        assert_eq!(code, EXIT_EDITOR_FAILED);
+    }
+
+    let err = diff_with(dir.path(), FailingComand).run().unwrap_err();
+    assert_eq!(err.exit_code(), EXIT_EDITOR_NOT_FOUND);
+
+    let mut prog = diff_with(dir.path(), TestComand::new());
+    prog.profile = Some("missing".into());
+    assert_eq!(prog.run().unwrap_err().exit_code(), EXIT_INVALID_ARGS);
+
+    let mut prog = diff_with(dir.path(), TestComand::new());
+    fs::remove_file(dir.path().join("base_cfg.txt")).unwrap();
+    assert_eq!(prog.run().unwrap_err().exit_code(), EXIT_IO_ERROR);
+
+    // action is done once
+    let mut prog = diff_with(dir.path(), TestComand::new());
+    fs::write(dir.path().join("base_cfg.txt"), "base_cfg").unwrap();
+    prog.run().unwrap();
+    let err = prog.run().unwrap_err();
+    assert!(matches!(err, Error::AlreadyRun));
+    assert_eq!(err.exit_code(), EXIT_INVALID_ARGS);
+}
+
+/// config that can't be read or parsed is invalid args
+#[test]
+fn test_config_exit_codes() {
+    let dir = tempdir().expect("tempdir");
+    let path = dir.path().join("config.toml");
+    let err = Config::from_file(&path).unwrap_err();
+    assert!(matches!(err, Error::ReadConfig { .. }));
+    assert_eq!(err.exit_code(), EXIT_INVALID_ARGS);
+
+    fs::write(&path, "unknown-key = 1\n").unwrap();
+    let err = Config::load(Some(&path)).unwrap_err();
+    assert!(matches!(err, Error::InvalidConfig { path: ref p, .. } if *p == path));
+    assert_eq!(err.exit_code(), EXIT_INVALID_ARGS);
+}
+
+/// failed `git config` is reported with its key (requires git)
+#[test]
+fn test_git_exit_codes() {
+    let dir = tempdir().expect("tempdir");
+    // parent of config file is missing
+    let file = dir.path().join("missing").join("gitconfig");
+    let entries = [("merge.tool".to_string(), "x".to_string())];
+    let err = git::install(&entries, Some(&file)).unwrap_err();
+    assert!(matches!(err, Error::GitConfig { ref key, .. } if key == "merge.tool"));
+    assert_eq!(err.exit_code(), EXIT_IO_ERROR);
+
+    let err = Error::GitLaunch {
+        source: io::ErrorKind::NotFound.into(),
+    };
+    assert_eq!(err.exit_code(), EXIT_IO_ERROR);
+    let err = Error::CurrentDir {
+        source: io::ErrorKind::NotFound.into(),
+    };
+    assert_eq!(err.exit_code(), EXIT_IO_ERROR);
+}
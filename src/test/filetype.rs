@@ -0,0 +1,109 @@
+use std::fs;
+
+use super::{prepare_diff, workspace_of};
+use crate::filetype::*;
+use tempfile::tempdir;
+
+#[test]
+fn test_classify_by_name() {
+    let rules = [];
+    assert_eq!(
+        classify("Документ.Заказ.Form.ФормаДокумента.xml", "", &rules),
+        FileType::Xml
+    );
+    assert_eq!(
+        classify("Обработка.Help.ru.html", "", &rules),
+        FileType::Html
+    );
+    assert_eq!(
+        classify("Отчет.TEMPLATE.Макет.txt", "", &rules),
+        FileType::Txt
+    );
+    assert_eq!(classify("Справочник.Query.txt", "", &rules), FileType::Sdbl);
+    // name wins over content
+    assert_eq!(
+        classify("Catalog.Module.txt", "<html>", &rules),
+        FileType::Bsl
+    );
+    // markers are whole segments, the last one decides, modules of objects are BSL
+    let modules = [
+        "Catalog.FormatSettings.ObjectModule.txt",
+        "Catalog.Helpdesk.ObjectModule.txt",
+        "Справочник.ФормаОплаты.МодульОбъекта.txt",
+        "Document.Queryset.ManagerModule.txt",
+        "CommonModule.TemplateUtils.Module.txt",
+        "Документ.Заказ.Form.ФормаДокумента.Form.Module.txt",
+        "CommonModule.Helpers.Module (File 2).txt",
+    ];
+    for name in modules {
+        assert_eq!(classify(name, "<html>", &rules), FileType::Bsl, "{name}");
+    }
+    assert_eq!(
+        classify("Document.Queryset.Template.Query.txt", "", &rules),
+        FileType::Sdbl
+    );
+}
+
+#[test]
+fn test_classify_by_content() {
+    let rules = [];
+    let cases = [
+        ("\u{feff}<?xml version=\"1.0\"?>\n<Form/>", FileType::Xml),
+        ("  <!DOCTYPE html>\n<html></html>", FileType::Html),
+        ("<HTML><body/></HTML>", FileType::Html),
+        (
+            "// отбор\nВЫБРАТЬ\n\tТовары.Ссылка\nИЗ Справочник.Товары КАК Товары",
+            FileType::Sdbl,
+        ),
+        ("select * from Catalog.Goods", FileType::Sdbl),
+        ("Процедура А()\nКонецПроцедуры", FileType::Bsl),
+        ("Выбрать = 1;", FileType::Bsl),
+        ("", FileType::Bsl),
+    ];
+    for (text, expected) in cases {
+        assert_eq!(classify("tmp123.txt", text, &rules), expected, "{text}");
+    }
+}
+
+/// configured rules go first, the longest marker ending at the same segment wins
+#[test]
+fn test_classify_rules() {
+    let rules = [
+        (".Template".to_string(), FileType::Xml),
+        (".Template.Схема".to_string(), FileType::Sdbl),
+    ];
+    assert_eq!(
+        classify("Отчет.Template.Макет.txt", "", &rules),
+        FileType::Xml
+    );
+    assert_eq!(
+        classify("Отчет.template.схема.txt", "", &rules),
+        FileType::Sdbl
+    );
+}
+
+#[test]
+fn test_file_type_extension() {
+    assert_eq!(FileType::Bsl.extension("os"), "os");
+    assert_eq!(FileType::Xml.extension("os"), "xml");
+    assert_eq!(FileType::Sdbl.language_id(), Some(SDBL_LANGUAGE_ID));
+    assert_eq!(FileType::Html.language_id(), None);
+}
+
+/// form is staged as xml
+#[test]
+fn test_cmd_diff_form() {
+    let dir = tempdir().expect("tempdir");
+    let base_cfg = dir.path().join("base_cfg.txt");
+    let second_cfg = dir.path().join("second_cfg.txt");
+
+    let mut prog = prepare_diff(&base_cfg, &second_cfg, false, false);
+    fs::write(&base_cfg, "<?xml version=\"1.0\"?>\n<Form/>").unwrap();
+    prog.run().unwrap();
+
+    let vscmd = prog.into_vscmd();
+    let args: Vec<_> = vscmd.into_iner().get_args().map(|a| a.to_owned()).collect();
+    let workspace = workspace_of(&args);
+    assert_eq!(args[args.len() - 2], workspace.join("base_cfg.xml"));
+    assert_eq!(args[args.len() - 1], workspace.join("second_cfg.xml"));
+}
@@ -0,0 +1,110 @@
+use clap::Parser;
+use std::{fs, path::Path, process::Command};
+
+use super::TestComand;
+use crate::git::*;
+use crate::*;
+use tempfile::tempdir;
+
+#[test]
+fn test_sh_quote() {
+    assert_eq!(sh_quote("/usr/bin/adapter"), "'/usr/bin/adapter'");
+    assert_eq!(
+        sh_quote(r"C:\Program Files\it's\adapter.exe"),
+        r"'C:\Program Files\it'\''s\adapter.exe'"
+    );
+}
+
+#[test]
+fn test_config_entries() {
+    let entries = config_entries("bsl", Path::new("/opt/adapter"), false);
+    assert_eq!(
+        entries[0],
+        (
+            "mergetool.bsl.cmd".to_string(),
+            r#"'/opt/adapter' git-merge "$LOCAL" "$REMOTE" "$BASE" "$MERGED""#.to_string()
+        )
+    );
+    assert_eq!(
+        entries[2].1,
+        r#"'/opt/adapter' git-diff "$LOCAL" "$REMOTE""#
+    );
+    assert_eq!(entries.len(), 4);
+
+    let entries = config_entries("bsl", Path::new("/opt/adapter"), true);
+    assert!(entries.contains(&("merge.tool".into(), "bsl".into())));
+    assert!(entries.contains(&("diff.tool".into(), "bsl".into())));
+}
+
+/// entries are written into given file (requires git)
+#[test]
+fn test_install_git() {
+    let dir = tempdir().expect("tempdir");
+    let file = dir.path().join("gitconfig");
+    fs::write(&file, "[user]\n\tname = someone\n").unwrap();
+
+    let cli = Cli::parse_from([
+        "adapter",
+        "install-git",
+        "--file",
+        file.to_str().unwrap(),
+        "--set-default",
+    ]);
+    assert!(!cli.command.needs_editor());
+    let mut prog = Program::new(cli, Config::default(), TestComand::new());
+    assert_eq!(prog.run().unwrap(), 0);
+
+    let get = |key: &str| {
+        let out = Command::new("git")
+            .args(["config", "--file", file.to_str().unwrap(), "--get", key])
+            .output()
+            .unwrap();
+        String::from_utf8(out.stdout)
+            .unwrap()
+            .trim_end()
+            .to_string()
+    };
+    assert!(get("mergetool.vscode-adapter.cmd")
+        .ends_with(r#" git-merge "$LOCAL" "$REMOTE" "$BASE" "$MERGED""#));
+    assert_eq!(get("mergetool.vscode-adapter.trustExitCode"), "true");
+    assert_eq!(get("difftool.vscode-adapter.trustExitCode"), "true");
+    assert_eq!(get("merge.tool"), "vscode-adapter");
+    assert_eq!(get("user.name"), "someone");
+}
+
+/// git-merge: result is seeded with $MERGED (merged by git), args are in git order
+#[test]
+fn test_cmd_git_merge() {
+    let dir = tempdir().expect("tempdir");
+    let [local, remote, base, merged] = ["LOCAL", "REMOTE", "BASE", "MERGED"].map(|name| {
+        let path = dir.path().join(format!("Module_{name}_1234.bsl"));
+        fs::write(&path, name).unwrap();
+        path
+    });
+    let cli = Cli::parse_from([
+        "adapter".as_ref(),
+        "git-merge".as_ref(),
+        local.as_os_str(),
+        remote.as_os_str(),
+        base.as_os_str(),
+        merged.as_os_str(),
+    ]);
+    assert!(cli.command.needs_editor());
+    let mut prog = Program::new(cli, Config::default(), TestComand::new());
+
+    // result is not changed by test editor: markers are left by git
+    fs::write(&merged, "<<<<<<< LOCAL\n=======\n>>>>>>> REMOTE\n").unwrap();
+    assert_eq!(prog.run().unwrap(), EXIT_CONFLICTS);
+
+    let vscmd = prog.into_vscmd();
+    let staged: Vec<String> = vscmd
+        .files
+        .iter()
+        .map(|(_, content)| String::from_utf8_lossy(content).into_owned())
+        .collect();
+    assert_eq!(staged[..3], ["LOCAL", "REMOTE", "BASE"]);
+    assert!(staged[3].starts_with("<<<<<<< LOCAL"));
+    for path in [local, remote, base] {
+        assert!(path.exists());
+    }
+}
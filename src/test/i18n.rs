@@ -0,0 +1,157 @@
+use std::collections::BTreeSet;
+use std::ffi::OsString;
+
+use clap::{Command, CommandFactory, FromArgMatches};
+
+use crate::i18n::*;
+use crate::*;
+
+/// `{name}` placeholders of `text`
+fn placeholders(text: &str) -> BTreeSet<&str> {
+    text.split('{')
+        .skip(1)
+        .filter_map(|part| part.split_once('}'))
+        .map(|(name, _)| name)
+        .collect()
+}
+
+/// help texts of `cmd` and its subcommands
+fn help_texts(cmd: &Command) -> Vec<String> {
+    let about = cmd.get_about().map(ToString::to_string);
+    let args = cmd
+        .get_arguments()
+        .filter_map(|arg| arg.get_help())
+        .map(ToString::to_string);
+    let mut texts: Vec<String> = about.into_iter().chain(args).collect();
+    for sub in cmd.get_subcommands() {
+        texts.extend(help_texts(sub));
+    }
+    texts
+}
+
+/// help texts of possible values of args of `cmd` and its subcommands
+fn value_help_texts(cmd: &Command) -> Vec<String> {
+    let mut texts: Vec<String> = cmd
+        .get_arguments()
+        .flat_map(|arg| arg.get_possible_values())
+        .filter_map(|value| value.get_help().map(ToString::to_string))
+        .collect();
+    for sub in cmd.get_subcommands() {
+        texts.extend(value_help_texts(sub));
+    }
+    texts
+}
+
+#[test]
+fn test_tr() {
+    assert_eq!(
+        tr_in(
+            Lang::Ru,
+            "merge result {path} is not changed",
+            &[("path", &"m.bsl")]
+        ),
+        "результат объединения m.bsl не изменен"
+    );
+    assert_eq!(
+        tr_in(
+            Lang::En,
+            "merge result {path} is not changed",
+            &[("path", &"m.bsl")]
+        ),
+        "merge result m.bsl is not changed"
+    );
+    // values are not expanded, unknown placeholders are kept
+    assert_eq!(
+        tr_in(
+            Lang::Ru,
+            "copy {from} -> {to}",
+            &[("from", &"{to}"), ("to", &"b")]
+        ),
+        "скопировать {to} -> b"
+    );
+    assert_eq!(tr_in(Lang::Ru, "{x} {", &[]), "{x} {");
+    // untranslated message is english
+    assert_eq!(
+        tr_in(Lang::Ru, "no such {message}", &[("message", &1)]),
+        "no such 1"
+    );
+}
+
+#[test]
+fn test_catalog() {
+    for (message, translation) in parse_catalog(RU_CATALOG) {
+        assert_eq!(
+            placeholders(&message),
+            placeholders(&translation),
+            "{message}"
+        );
+    }
+    // help of every arg is translated
+    for text in help_texts(&Cli::command()) {
+        assert_ne!(translate(Lang::Ru, &text), text);
+    }
+    // and help of possible values (names of editors are kept)
+    let catalog = parse_catalog(RU_CATALOG);
+    for text in value_help_texts(&Cli::command()) {
+        assert!(catalog.contains_key(&text), "{text}");
+    }
+}
+
+#[test]
+fn test_lang_from_args() {
+    let args = |args: &[&str]| args.iter().map(OsString::from).collect::<Vec<_>>();
+    assert_eq!(
+        lang_from_args(args(&["adapter", "--lang", "ru", "diff"])),
+        Some(Lang::Ru)
+    );
+    assert_eq!(
+        lang_from_args(args(&["adapter", "--lang=EN"])),
+        Some(Lang::En)
+    );
+    assert_eq!(lang_from_args(args(&["adapter", "--lang", "de"])), None);
+    assert_eq!(lang_from_args(args(&["adapter", "--", "--lang=ru"])), None);
+    assert_eq!(lang_from_args(args(&["adapter", "diff", "a", "b"])), None);
+}
+
+#[test]
+fn test_localized_help() {
+    let mut cmd = localize_command(Cli::command(), Lang::Ru);
+    cmd.clone().debug_assert();
+    let help = cmd.render_help().to_string();
+    assert!(help.starts_with("Адаптер"), "{help}");
+    assert!(help.contains("Использование:"));
+    assert!(help.contains("Команды:"));
+    assert!(help.contains("  diff         сравнить 2 файла"));
+    assert!(help.contains("-h, --help"));
+    assert!(!help.contains("Options"));
+
+    let merge = cmd.find_subcommand_mut("merge").unwrap();
+    let help = merge.render_help().to_string();
+    assert!(help.contains("Аргументы:\n  <BASE_CFG>"), "{help}");
+    assert!(help.find("<SECOND_CFG> ") < help.find("<OLD_VENDOR_CFG> "));
+    assert!(help.find("Аргументы:") < help.find("Параметры:"));
+    assert!(help.contains("-V, --version"));
+
+    // possible values are listed in russian
+    let help = cmd.render_long_help().to_string();
+    assert!(help.contains("Возможные значения:\n"), "{help}");
+    assert!(help.contains("- utf8-bom: UTF-8 с BOM (по умолчанию в выгрузке 1С)"));
+    assert!(!help.contains("ossible values"));
+    let help = cmd.render_help().to_string();
+    assert!(help.contains("[возможные значения: en, ru]"), "{help}");
+    assert!(!help.contains("ossible values"));
+
+    // localized args are parsed as usual
+    let matches = localize_command(Cli::command(), Lang::Ru)
+        .try_get_matches_from(["adapter", "--lang", "ru", "install-git"])
+        .unwrap();
+    let cli = Cli::from_arg_matches(&matches).unwrap();
+    assert_eq!(cli.lang, Some(Lang::Ru));
+    assert!(
+        matches!(cli.command, Action::InstallGit { name, .. } if name == git::DEFAULT_TOOL_NAME)
+    );
+    let err = localize_command(Cli::command(), Lang::Ru)
+        .try_get_matches_from(["adapter", "--help"])
+        .unwrap_err();
+    assert_eq!(err.kind(), clap::error::ErrorKind::DisplayHelp);
+}
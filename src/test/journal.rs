@@ -0,0 +1,174 @@
+use std::fs;
+
+use tempfile::tempdir;
+
+use super::{exit::ExitingComand, staging::FailingComand, TestComand};
+use crate::exit::*;
+use crate::journal::*;
+use crate::*;
+
+/// base, new vendor, old vendor and result trees in `dir`, 2 files changed on both sides
+fn write_trees(dir: &Path) -> [PathBuf; 4] {
+    let dirs = ["base", "new", "old", "merged"].map(|name| dir.join(name));
+    let files = [
+        [("a.txt", "A\n"), ("b.txt", "B\n"), ("c.txt", "c\n")],
+        [("a.txt", "1\n"), ("b.txt", "2\n"), ("c.txt", "C\n")],
+        [("a.txt", "a\n"), ("b.txt", "b\n"), ("c.txt", "c\n")],
+    ];
+    for (dir, files) in dirs.iter().zip(files) {
+        fs::create_dir_all(dir).unwrap();
+        for (name, content) in files {
+            fs::write(dir.join(name), content).unwrap();
+        }
+    }
+    dirs
+}
+
+/// merge in one editor window with `journal`
+fn merge_in_workspace(dirs: &[PathBuf; 4], journal: &Path) -> Action {
+    let mut action = merge_dir(dirs, journal);
+    if let Action::MergeDir { workspace, .. } = &mut action {
+        *workspace = true;
+    }
+    action
+}
+
+fn merge_dir(dirs: &[PathBuf; 4], journal: &Path) -> Action {
+    let [base_dir, second_dir, old_vendor_dir, merged_dir] = dirs.clone();
+    Action::MergeDir {
+        base_dir,
+        second_dir,
+        old_vendor_dir,
+        merged_dir,
+        from_second: false,
+        workspace: false,
+        journal: Some(journal.to_path_buf()),
+    }
+}
+
+#[test]
+fn test_journal_file() {
+    let dir = tempdir().expect("tempdir");
+    let path = dir.path().join("journal.json");
+    let dirs = ["b", "s", "o", "m"].map(PathBuf::from);
+    assert_eq!(Journal::load(&path).unwrap(), None);
+
+    let mut journal = Journal::open(&path, &dirs).unwrap();
+    journal.set(Path::new("x/a.bsl"), FileState::Pending);
+    journal.set(Path::new("b.bsl"), FileState::AutoMerged);
+    journal.set(Path::new("x/a.bsl"), FileState::Merged);
+    journal.save().unwrap();
+    assert!(fs::read_to_string(&path)
+        .unwrap()
+        .contains(r#""b.bsl": "auto-merged""#));
+
+    let loaded = Journal::open(&path, &dirs).unwrap();
+    assert_eq!(loaded, journal);
+    assert_eq!(loaded.state(Path::new("x/a.bsl")), Some(FileState::Merged));
+    assert_eq!(loaded.state(Path::new("c.bsl")), None);
+    assert_eq!(loaded.counts().get(&FileState::Merged), Some(&1));
+
+    let other = ["b", "s", "o", "b"].map(PathBuf::from);
+    let err = Journal::open(&path, &other).unwrap_err();
+    assert!(matches!(err, Error::JournalMismatch { .. }));
+    assert_eq!(err.exit_code(), EXIT_INVALID_ARGS);
+
+    fs::write(&path, "{").unwrap();
+    assert!(matches!(
+        Journal::load(&path).unwrap_err(),
+        Error::Journal { .. }
+    ));
+}
+
+#[test]
+fn test_resume_merge() {
+    let dir = tempdir().expect("tempdir");
+    let dirs = write_trees(dir.path());
+    let journal = dir.path().join("journal.json");
+    let status = || {
+        let action = Action::Status {
+            journal: journal.clone(),
+        };
+        Program::builder(action, TestComand::new()).build().run()
+    };
+    assert!(status().is_err());
+
+    // editor can't be launched: changed files are left pending
+    let mut prog = Program::builder(merge_dir(&dirs, &journal), FailingComand).build();
+    assert!(prog.run().is_err());
+    let saved = Journal::load(&journal).unwrap().unwrap();
+    assert_eq!(saved.state(Path::new("a.txt")), Some(FileState::Pending));
+    assert_eq!(saved.state(Path::new("b.txt")), Some(FileState::Pending));
+    assert_eq!(saved.state(Path::new("c.txt")), Some(FileState::AutoMerged));
+    assert_eq!(status().unwrap(), EXIT_CONFLICTS);
+
+    // resumed with pending files only
+    fs::remove_file(dirs[3].join("c.txt")).unwrap();
+    let mut prog = Program::builder(merge_dir(&dirs, &journal), TestComand::new()).build();
+    assert_eq!(prog.run().unwrap(), EXIT_SUCCESS);
+    assert_eq!(prog.into_vscmd().files.len(), 8);
+    assert!(!dirs[3].join("c.txt").exists());
+    let saved = Journal::load(&journal).unwrap().unwrap();
+    assert_eq!(saved.counts().get(&FileState::Merged), Some(&2));
+    assert_eq!(status().unwrap(), EXIT_SUCCESS);
+
+    // nothing left to merge
+    let mut prog = Program::builder(merge_dir(&dirs, &journal), TestComand::new()).build();
+    assert_eq!(prog.run().unwrap(), EXIT_SUCCESS);
+    assert!(prog.into_vscmd().files.is_empty());
+}
+
+#[test]
+fn test_journal_skipped() {
+    let dir = tempdir().expect("tempdir");
+    let dirs = write_trees(dir.path());
+    let journal = dir.path().join("journal.json");
+
+    // conflict markers are left in one editor window
+    let mut prog = Program::builder(merge_in_workspace(&dirs, &journal), TestComand::new())
+        .auto_merge(true)
+        .build();
+    assert_eq!(prog.run().unwrap(), EXIT_CONFLICTS);
+    let saved = Journal::load(&journal).unwrap().unwrap();
+    assert_eq!(saved.counts().get(&FileState::Skipped), Some(&2));
+
+    // resumed: skipped files are merged again from their results, markers are kept
+    let conflicted = fs::read(dirs[3].join("a.txt")).unwrap();
+    assert!(conflicted.starts_with(b"<<<<<<<"));
+    let mut prog = Program::builder(merge_dir(&dirs, &journal), TestComand::new()).build();
+    assert_eq!(prog.run().unwrap(), EXIT_CONFLICTS);
+    let files = prog.into_vscmd().files;
+    assert_eq!(files.len(), 8);
+    assert!(files[3].1.ends_with(&conflicted[..]));
+    assert_eq!(fs::read(dirs[3].join("a.txt")).unwrap(), conflicted);
+    let saved = Journal::load(&journal).unwrap().unwrap();
+    assert_eq!(saved.counts().get(&FileState::Skipped), Some(&2));
+
+    // resolved outside of editor, other one is still in conflict
+    fs::write(dirs[3].join("a.txt"), "1\n").unwrap();
+    let mut prog = Program::builder(merge_in_workspace(&dirs, &journal), TestComand::new()).build();
+    assert_eq!(prog.run().unwrap(), EXIT_CONFLICTS);
+    assert_eq!(fs::read(dirs[3].join("a.txt")).unwrap(), b"1\n");
+    let saved = Journal::load(&journal).unwrap().unwrap();
+    assert_eq!(saved.state(Path::new("a.txt")), Some(FileState::Merged));
+    assert_eq!(saved.state(Path::new("b.txt")), Some(FileState::Skipped));
+}
+
+/// results are not written back if editor of workspace fails, files are left pending
+#[test]
+fn test_workspace_editor_failure() {
+    let dir = tempdir().expect("tempdir");
+    let dirs = write_trees(dir.path());
+    let journal = dir.path().join("journal.json");
+
+    // killed by signal on unix, exit code 9 on windows
+    let mut prog =
+        Program::builder(merge_in_workspace(&dirs, &journal), ExitingComand::new(9)).build();
+    assert_eq!(prog.run().unwrap(), EXIT_EDITOR_FAILED);
+    assert!(!dirs[3].join("a.txt").exists());
+    assert!(!dirs[3].join("b.txt").exists());
+    let saved = Journal::load(&journal).unwrap().unwrap();
+    assert_eq!(saved.state(Path::new("a.txt")), Some(FileState::Pending));
+    assert_eq!(saved.state(Path::new("b.txt")), Some(FileState::Pending));
+    assert_eq!(saved.state(Path::new("c.txt")), Some(FileState::AutoMerged));
+}
@@ -0,0 +1,70 @@
+use std::fs;
+
+use log::{Level, LevelFilter, Log, Record};
+use tempfile::tempdir;
+
+use crate::config::Config;
+use crate::error::Error;
+use crate::logging::*;
+
+#[test]
+fn test_log_level() {
+    assert_eq!(level(0), LevelFilter::Info);
+    assert_eq!(level(1), LevelFilter::Debug);
+    assert_eq!(level(5), LevelFilter::Trace);
+
+    let config: Config = toml::from_str("log-level = \"warn\"\nlog-dir = \"logs\"").unwrap();
+    assert_eq!(config.log_level, Some(LevelFilter::Warn));
+    assert_eq!(config.log_dir.unwrap().to_str(), Some("logs"));
+}
+
+#[test]
+fn test_log_rotate() {
+    let dir = tempdir().expect("tempdir");
+    let read = |name: &str| fs::read_to_string(dir.path().join(name)).ok();
+
+    // small file is kept
+    fs::write(dir.path().join("a.log"), "1").unwrap();
+    rotate(dir.path(), "a.log", 1, 2).unwrap();
+    assert_eq!(read("a.log").as_deref(), Some("1"));
+
+    for text in ["22", "333", "4444"] {
+        rotate(dir.path(), "a.log", 1, 2).unwrap();
+        fs::write(dir.path().join("a.log"), text).unwrap();
+    }
+    rotate(dir.path(), "a.log", 1, 2).unwrap();
+    assert_eq!(read("a.log"), None);
+    assert_eq!(read("a.log.1").as_deref(), Some("4444"));
+    assert_eq!(read("a.log.2").as_deref(), Some("333"));
+    assert_eq!(read("a.log.3"), None);
+}
+
+#[test]
+fn test_file_logger() {
+    let dir = tempdir().expect("tempdir");
+    let logger = FileLogger::open(&dir.path().join("logs"), LevelFilter::Info).unwrap();
+    for (level, message) in [(Level::Info, "launch"), (Level::Debug, "staged")] {
+        logger.log(
+            &Record::builder()
+                .level(level)
+                .args(format_args!("{message}"))
+                .build(),
+        );
+    }
+    logger.flush();
+
+    let text = fs::read_to_string(dir.path().join("logs").join(LOG_FILE)).unwrap();
+    let lines: Vec<&str> = text.lines().collect();
+    assert_eq!(lines.len(), 1);
+    assert!(lines[0].ends_with(&format!("[{}] INFO  launch", std::process::id())));
+}
+
+/// log dir that can't be created is reported with its path
+#[test]
+fn test_log_init_error() {
+    let dir = tempdir().expect("tempdir");
+    let file = dir.path().join("file");
+    fs::write(&file, "").unwrap();
+    let err = init(&file.join("logs"), LevelFilter::Info).unwrap_err();
+    assert!(matches!(err, Error::Log { dir, .. } if dir == file.join("logs")));
+}
@@ -0,0 +1,151 @@
+use std::fs;
+
+use super::prepare_merge;
+use crate::encoding::{encode, TextEncoding};
+use crate::merge3::*;
+use crate::*;
+use tempfile::tempdir;
+
+const LABELS: Labels = Labels {
+    ours: "base",
+    ancestor: "old",
+    theirs: "second",
+};
+
+const OLD: &str =
+    "Процедура А()\n    А = 1;\nКонецПроцедуры\n\nПроцедура Б()\n    Б = 1;\nКонецПроцедуры\n";
+
+#[test]
+fn test_merge_clean() {
+    // we changed А, vendor changed Б and added В
+    let ours = OLD.replace("А = 1", "А = 2");
+    let theirs = OLD.replace("Б = 1", "Б = 2") + "\nПроцедура В()\nКонецПроцедуры\n";
+    let expected = ours.replace("Б = 1", "Б = 2") + "\nПроцедура В()\nКонецПроцедуры\n";
+    assert_eq!(merge(OLD, &ours, &theirs, LABELS), Merged::Clean(expected));
+
+    // same change on both sides, deletion on one side
+    let ours = OLD.replace("А = 1", "А = 2");
+    let theirs = OLD.replace("А = 1", "А = 2").replace("    Б = 1;\n", "");
+    assert_eq!(
+        merge(OLD, &ours, &theirs, LABELS),
+        Merged::Clean(theirs.clone())
+    );
+
+    // not changed
+    assert_eq!(merge(OLD, OLD, OLD, LABELS), Merged::Clean(OLD.into()));
+    assert_eq!(merge("", "", "", LABELS), Merged::Clean("".into()));
+}
+
+#[test]
+fn test_merge_conflicts() {
+    let ours = OLD.replace("А = 1", "А = 2");
+    let theirs = OLD.replace("А = 1", "А = 3").replace("Б = 1", "Б = 3");
+    let expected = "Процедура А()
+<<<<<<< base
+    А = 2;
+||||||| old
+    А = 1;
+=======
+    А = 3;
+>>>>>>> second
+КонецПроцедуры
+
+Процедура Б()
+    Б = 3;
+КонецПроцедуры
+";
+    assert_eq!(
+        merge(OLD, &ours, &theirs, LABELS),
+        Merged::Conflicts {
+            text: expected.into(),
+            count: 1
+        }
+    );
+}
+
+/// markers are on own lines if last line has no line ending, line endings of our file are used
+#[test]
+fn test_merge_conflicts_line_endings() {
+    let merged = merge("a\r\nb", "a\r\nc", "a\r\nd", LABELS);
+    let expected =
+        "a\r\n<<<<<<< base\r\nc\r\n||||||| old\r\nb\r\n=======\r\nd\r\n>>>>>>> second\r\n";
+    assert_eq!(
+        merged,
+        Merged::Conflicts {
+            text: expected.into(),
+            count: 1
+        }
+    );
+}
+
+/// clean merge is written to result in source encoding, editor is not launched
+#[test]
+fn test_cmd_merge_auto() {
+    let dir = tempdir().expect("tempdir");
+    let base_cfg = dir.path().join("base_cfg.txt");
+    let second_cfg = dir.path().join("second_cfg.txt");
+    let old_vendor_cfg = dir.path().join("old_vendor_cfg.txt");
+    let merged = dir.path().join("merged.txt");
+
+    let mut prog = prepare_merge(
+        &base_cfg,
+        &second_cfg,
+        &old_vendor_cfg,
+        &merged,
+        false,
+        false,
+        false,
+    );
+    prog.auto_merge = true;
+    let ours = OLD.replace("А = 1", "А = 2");
+    let theirs = OLD.replace("Б = 1", "Б = 2");
+    fs::write(&old_vendor_cfg, encode(OLD, TextEncoding::Cp1251)).unwrap();
+    fs::write(&base_cfg, encode(&ours, TextEncoding::Cp1251)).unwrap();
+    fs::write(&second_cfg, encode(&theirs, TextEncoding::Utf8Bom)).unwrap();
+
+    assert_eq!(prog.run().unwrap(), 0);
+
+    let expected = ours.replace("Б = 1", "Б = 2");
+    assert_eq!(
+        fs::read(&merged).unwrap(),
+        encode(&expected, TextEncoding::Cp1251)
+    );
+    assert!(prog.into_vscmd().files.is_empty());
+    assert!(base_cfg.exists());
+}
+
+/// editor is opened with conflict markers in result
+#[test]
+fn test_cmd_merge_auto_conflicts() {
+    let dir = tempdir().expect("tempdir");
+    let base_cfg = dir.path().join("base_cfg.txt");
+    let second_cfg = dir.path().join("second_cfg.txt");
+    let old_vendor_cfg = dir.path().join("old_vendor_cfg.txt");
+    let merged = dir.path().join("merged.txt");
+
+    let mut prog = prepare_merge(
+        &base_cfg,
+        &second_cfg,
+        &old_vendor_cfg,
+        &merged,
+        false,
+        false,
+        true,
+    );
+    prog.auto_merge = true;
+    let ours = OLD.replace("А = 1", "А = 2");
+    let theirs = OLD.replace("А = 1", "А = 3");
+    fs::write(&old_vendor_cfg, OLD).unwrap();
+    fs::write(&base_cfg, &ours).unwrap();
+    fs::write(&second_cfg, &theirs).unwrap();
+
+    // result is not changed by test editor
+    assert_eq!(prog.run().unwrap(), EXIT_CONFLICTS);
+    let result = fs::read_to_string(&merged).unwrap();
+    assert!(result.contains("<<<<<<< base_cfg.txt\n"));
+    assert!(result.contains(">>>>>>> second_cfg.txt\n"));
+
+    // all 4 files are passed to editor, sources are moved back
+    assert_eq!(prog.into_vscmd().files.len(), 4);
+    assert_eq!(fs::read_to_string(&base_cfg).unwrap(), ours);
+}
@@ -0,0 +1,192 @@
+use std::{
+    fs,
+    path::{Path, PathBuf, MAIN_SEPARATOR},
+};
+
+use tempfile::tempdir;
+
+use crate::paths::*;
+use crate::test::prepare_diff;
+
+const WIN_SEPARATORS: &[char] = &['\\', '/'];
+
+#[test]
+fn test_strip_quotes() {
+    assert_eq!(strip_quotes(r#""C:\Temp\mrg/a.txt""#), r"C:\Temp\mrg/a.txt");
+    assert_eq!(strip_quotes(r#" "C:\Temp\a.txt "#), r"C:\Temp\a.txt");
+    // `"%~1\"` style trailing quote
+    assert_eq!(strip_quotes(r#"C:\Temp\mrg""#), r"C:\Temp\mrg");
+    assert_eq!(strip_quotes("a.txt"), "a.txt");
+}
+
+/// real path shapes from the platform
+#[test]
+fn test_normalize_separators_platform() {
+    let cases = [
+        (
+            r"C:\Users\zeegin\AppData\Local\Temp\mrg/CommonModule.DistributedModule.Module(File C__Users_zeegin_Downloads_1Cv8_1.0.2.cf).txt",
+            r"C:\Users\zeegin\AppData\Local\Temp\mrg\CommonModule.DistributedModule.Module(File C__Users_zeegin_Downloads_1Cv8_1.0.2.cf).txt",
+        ),
+        (
+            r"C:\Users\user\AppData\Local\Temp\\mrg//Catalog.Items.ObjectModule.txt",
+            r"C:\Users\user\AppData\Local\Temp\mrg\Catalog.Items.ObjectModule.txt",
+        ),
+        (
+            r"\\server\share/mrg/Module.txt",
+            r"\\server\share\mrg\Module.txt",
+        ),
+        ("C:/Temp/mrg/Module.txt", r"C:\Temp\mrg\Module.txt"),
+    ];
+    for (raw, expected) in cases {
+        assert_eq!(normalize_separators(raw, WIN_SEPARATORS, '\\'), expected);
+    }
+
+    assert_eq!(
+        normalize_separators("/tmp//mrg/Module.txt", &['/'], '/'),
+        "/tmp/mrg/Module.txt"
+    );
+}
+
+#[test]
+fn test_clean() {
+    assert_eq!(
+        clean(Path::new("/tmp/./mrg/../mrg/Module.txt")),
+        PathBuf::from("/tmp/mrg/Module.txt")
+    );
+    assert_eq!(clean(Path::new("/../a.txt")), PathBuf::from("/a.txt"));
+    assert_eq!(clean(Path::new("../a.txt")), PathBuf::from("../a.txt"));
+}
+
+#[test]
+fn test_normalize() {
+    let cwd = Path::new(if cfg!(windows) { r"C:\work" } else { "/work" });
+    let sep = MAIN_SEPARATOR;
+
+    let relative = format!("\"mrg{sep}.{sep}Module.txt\"");
+    assert_eq!(
+        normalize(Path::new(&relative), cwd),
+        cwd.join("mrg").join("Module.txt")
+    );
+
+    let absolute = cwd.join("mrg").join("..").join("Module.txt");
+    assert_eq!(normalize(&absolute, cwd), cwd.join("Module.txt"));
+
+    #[cfg(windows)]
+    assert_eq!(
+        normalize(Path::new(r#""C:\Temp\mrg/Module.txt""#), cwd),
+        PathBuf::from(r"C:\Temp\mrg\Module.txt")
+    );
+}
+
+/// quoted paths with redundant separators reach the editor normalized
+#[test]
+fn test_cmd_diff_quoted_paths() {
+    let dir = tempdir().expect("tempdir");
+    let base_cfg = dir.path().join("base_cfg.txt");
+    let second_cfg = dir.path().join("second_cfg.txt");
+    let mut prog = prepare_diff(&base_cfg, &second_cfg, true, false);
+
+    let quoted = format!(
+        "\"{}{MAIN_SEPARATOR}{MAIN_SEPARATOR}second_cfg.txt\"",
+        dir.path().display()
+    );
+    if let Some(crate::Action::Diff { second_cfg, .. }) = prog.action.as_mut() {
+        *second_cfg = PathBuf::from(quoted);
+    }
+
+    assert_eq!(prog.run().unwrap(), 0);
+    assert!(fs::metadata(&second_cfg).is_ok());
+
+    let vscmd = prog.into_vscmd();
+    let (staged, _) = vscmd.files.last().unwrap();
+    assert_eq!(staged.file_name().unwrap(), "second_cfg.bsl");
+}
+
+#[test]
+fn test_sanitize_file_name() {
+    assert_eq!(
+        sanitize_file_name("Основная конфигурация").as_deref(),
+        Some("Основная конфигурация")
+    );
+    assert_eq!(
+        sanitize_file_name(r#"Vendor: "1C:ERP" 2.5/3.1?"#).as_deref(),
+        Some("Vendor_ _1C_ERP_ 2.5_3.1_")
+    );
+    assert_eq!(
+        sanitize_file_name("Version 8.3.1. ").as_deref(),
+        Some("Version 8.3.1")
+    );
+    assert_eq!(sanitize_file_name(" .. "), None);
+    assert_eq!(
+        sanitize_file_name(&"a".repeat(300)).unwrap().len(),
+        MAX_TITLE_LEN
+    );
+}
+
+/// file names not in UTF-8 (legacy Cyrillic in CP1251) reach editor unchanged
+#[cfg(target_os = "linux")]
+#[test]
+fn test_non_utf8_file_names() {
+    use std::ffi::{OsStr, OsString};
+    use std::os::unix::ffi::OsStrExt;
+
+    use crate::profile::Profile;
+    use crate::test::{prepare_merge, TestComand};
+    use crate::Program;
+
+    // "Модуль" in CP1251
+    let stem = OsStr::from_bytes(b"\xcc\xee\xe4\xf3\xeb\xfc");
+    let name = |suffix: &str| {
+        let mut name = stem.to_os_string();
+        name.push(suffix);
+        name
+    };
+    let dir = tempdir().expect("tempdir");
+    let [base_cfg, second_cfg, old_vendor_cfg, merged] =
+        ["_base.txt", "_second.txt", "_old.txt", "_merged.txt"].map(|s| dir.path().join(name(s)));
+
+    for rename_files in [false, true] {
+        let mut prog = prepare_merge(
+            &base_cfg,
+            &second_cfg,
+            &old_vendor_cfg,
+            &merged,
+            false,
+            false,
+            rename_files,
+        );
+        assert_eq!(prog.run().unwrap(), 0);
+
+        let vscmd = prog.into_vscmd();
+        let staged: Vec<&Path> = vscmd.files.iter().map(|(p, _)| p.as_path()).collect();
+        let names: Vec<&OsStr> = staged.iter().filter_map(|p| p.file_name()).collect();
+        let expected = ["_base.bsl", "_second.bsl", "_old.bsl", "_merged.bsl"].map(name);
+        assert_eq!(names, expected);
+        assert_eq!(fs::read_to_string(&merged).unwrap(), "base_cfg");
+        assert_eq!(fs::read_to_string(&base_cfg).unwrap(), "base_cfg");
+    }
+
+    // in editor args template
+    let prog = prepare_diff(&base_cfg, &second_cfg, false, false);
+    let mut prog = Program::new_test(prog.action.unwrap(), TestComand::new(), false, false);
+    let profile = Profile {
+        diff_args: Some(vec!["--left={base}".into(), "{second}".into()]),
+        ..Default::default()
+    };
+    prog.profiles.insert("test".into(), profile);
+    prog.profile = Some("test".into());
+    assert_eq!(prog.run().unwrap(), 0);
+
+    let vscmd = prog.into_vscmd();
+    let args: Vec<&Path> = vscmd.files.iter().map(|(p, _)| p.as_path()).collect();
+    assert_eq!(args[0].file_name(), Some(name("_second.bsl").as_os_str()));
+    let args: Vec<OsString> = vscmd.into_iner().get_args().map(OsStr::to_owned).collect();
+    let mut left = OsString::from("--left=");
+    left.push(
+        Path::new(&args[1])
+            .parent()
+            .unwrap()
+            .join(name("_base.bsl")),
+    );
+    assert_eq!(args[0], left);
+}
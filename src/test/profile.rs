@@ -0,0 +1,126 @@
+use clap::Parser;
+use std::{ffi::OsStr, fs, path::PathBuf};
+
+use super::TestComand;
+use crate::config::Config;
+use crate::profile::*;
+use crate::*;
+use tempfile::tempdir;
+
+/// Perforce passes `%b %1 %2 %r`, editor gets result first
+const P4_PROFILE: &str = r#"
+profile = "p4"
+
+[profiles.p4]
+merge = ["old-vendor", "second", "base", "merged"]
+diff = ["second", "base"]
+merge-args = ["--result={merged}", "{base}", "{second}", "{old-vendor}"]
+diff-args = ["--cwd={workspace}", "{base}", "{second}"]
+"#;
+
+#[test]
+fn test_arrange() {
+    let args = ["1", "2", "3", "4"].map(PathBuf::from);
+    let order = [Role::OldVendor, Role::Second, Role::Base, Role::Merged];
+    assert_eq!(
+        arrange(args.clone(), &order, MERGE_ROLES).unwrap(),
+        ["3", "2", "1", "4"].map(PathBuf::from)
+    );
+    // each role once
+    let order = [Role::Base, Role::Second, Role::Base, Role::Merged];
+    assert!(arrange(args.clone(), &order, MERGE_ROLES).is_err());
+    assert!(arrange(args, &order[..3], MERGE_ROLES).is_err());
+}
+
+#[test]
+fn test_expand() {
+    let template = ["--out={merged}", "{workspace}", "{base}", "{unknown}"].map(String::from);
+    let roles = [
+        (Role::Base, "a.bsl".as_ref()),
+        (Role::Merged, "m.bsl".as_ref()),
+    ];
+    assert_eq!(
+        expand(&template, "ws".as_ref(), &roles),
+        ["--out=m.bsl", "ws", "a.bsl", "{unknown}"]
+    );
+
+    // values are not expanded again
+    let template = ["{workspace}{base}".to_string()];
+    let roles = [(Role::Base, "{workspace}".as_ref())];
+    assert_eq!(expand(&template, "ws".as_ref(), &roles), ["ws{workspace}"]);
+}
+
+#[test]
+fn test_config_profiles() {
+    let config: Config = toml::from_str(P4_PROFILE).unwrap();
+    assert_eq!(config.profile.as_deref(), Some("p4"));
+    let profile = &config.profiles.as_ref().unwrap()["p4"];
+    assert_eq!(profile.diff, Some(vec![Role::Second, Role::Base]));
+    assert!(toml::from_str::<Config>("[profiles.x]\nmerge = [\"local\"]").is_err());
+
+    // profiles from all files are kept
+    let other: Config = toml::from_str("[profiles.edt]\ndiff = [\"base\", \"second\"]").unwrap();
+    let merged = config.merge(other);
+    let profiles = merged.profiles.unwrap();
+    assert!(profiles.contains_key("p4"));
+    assert!(profiles.contains_key("edt"));
+    assert_eq!(merged.profile.as_deref(), Some("p4"));
+}
+
+/// staged file name from editor args
+fn file_names(vscmd: TestComand) -> Vec<String> {
+    vscmd
+        .into_iner()
+        .get_args()
+        .map(|arg| {
+            let arg = arg.to_str().unwrap();
+            match arg.split_once('=') {
+                Some((key, path)) => format!("{key}={}", file_name(path)),
+                None => file_name(arg),
+            }
+        })
+        .collect()
+}
+
+fn file_name(path: &str) -> String {
+    let path = PathBuf::from(path);
+    if path.is_absolute() {
+        path.file_name().map(OsStr::to_string_lossy).unwrap().into()
+    } else {
+        path.display().to_string()
+    }
+}
+
+#[test]
+fn test_cmd_merge_profile() {
+    let dir = tempdir().expect("tempdir");
+    let config: Config = toml::from_str(P4_PROFILE).unwrap();
+    let [base, theirs, yours, result] = ["base", "theirs", "yours", "result"].map(|name| {
+        let path = dir.path().join(format!("{name}.txt"));
+        fs::write(&path, name).unwrap();
+        path
+    });
+    let args = [&base, &theirs, &yours, &result].map(|p| p.to_str().unwrap());
+
+    let cli = Cli::parse_from(["adapter", "merge"].into_iter().chain(args));
+    let mut prog = Program::new(cli, config.clone(), TestComand::new());
+    assert_eq!(prog.run().unwrap(), 0);
+    // seed is %baseCfg (yours)
+    assert_eq!(fs::read_to_string(&result).unwrap(), "yours");
+    assert_eq!(
+        file_names(prog.into_vscmd()),
+        ["--result=result.bsl", "yours.bsl", "theirs.bsl", "base.bsl"]
+    );
+
+    let cli = Cli::parse_from(["adapter", "diff", args[1], args[2]]);
+    let mut prog = Program::new(cli, config.clone(), TestComand::new());
+    assert_eq!(prog.run().unwrap(), 0);
+    let names = file_names(prog.into_vscmd());
+    assert!(names[0].starts_with("--cwd=vscode-adapter-"));
+    assert_eq!(names[1..], ["yours.bsl", "theirs.bsl"]);
+
+    // unknown profile
+    let cli = Cli::parse_from(["adapter", "-p", "edt", "diff", args[1], args[2]]);
+    let mut prog = Program::new(cli, config, TestComand::new());
+    assert!(prog.run().is_err());
+}
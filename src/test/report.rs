@@ -0,0 +1,120 @@
+use std::fs;
+
+use super::prepare_diff;
+use crate::report::*;
+use crate::*;
+use tempfile::tempdir;
+
+const OLD: &str = "Перем А;
+
+Процедура Первая()
+    А = 1;
+    Б = 2;
+    В = 3;
+    Г = 4;
+    Д = 5;
+КонецПроцедуры
+";
+
+/// print diff to string
+fn diff(format: DiffFormat, old: &str, new: &str) -> (bool, String) {
+    let mut out = vec![];
+    let differ = write_diff(&mut out, format, "old.bsl", "new.bsl", old, new).unwrap();
+    (differ, String::from_utf8(out).unwrap())
+}
+
+#[test]
+fn test_diff_unified() {
+    let new = OLD.replace("Д = 5", "Д = 6");
+    let expected = "--- old.bsl
++++ new.bsl
+@@ -5,5 +5,5 @@ Процедура Первая()
+     Б = 2;
+     В = 3;
+     Г = 4;
+-    Д = 5;
++    Д = 6;
+ КонецПроцедуры
+";
+    assert_eq!(
+        diff(DiffFormat::Unified, OLD, &new),
+        (true, expected.into())
+    );
+    assert_eq!(diff(DiffFormat::Unified, OLD, OLD), (false, "".into()));
+
+    // outside of method, no line ending
+    let expected = "--- old.bsl
++++ new.bsl
+@@ -1 +1 @@
+-a
+\\ No newline at end of file
++b
+\\ No newline at end of file
+";
+    assert_eq!(diff(DiffFormat::Unified, "a", "b"), (true, expected.into()));
+    let expected = "--- old.bsl\n+++ new.bsl\n@@ -0,0 +1 @@\n+b\n";
+    assert_eq!(
+        diff(DiffFormat::Unified, "", "b\n"),
+        (true, expected.into())
+    );
+}
+
+#[test]
+fn test_diff_side_by_side() {
+    let new = OLD.replace("Д = 5", "Д = 6").replace("    Б = 2;\n", "");
+    let (differ, out) = diff(DiffFormat::SideBySide, OLD, &new);
+    assert!(differ);
+    let lines: Vec<&str> = out.lines().map(str::trim_end).collect();
+    let pad = |text: &str| format!("{text:<SIDE_WIDTH$}");
+    assert_eq!(lines[0], format!("{} | new.bsl", pad("old.bsl")));
+    assert_eq!(lines[1], "@@ -2,8 +2,7 @@ Процедура Первая()");
+    assert_eq!(lines[5], format!("{} <", pad("    Б = 2;")));
+    assert_eq!(lines[7], format!("{}       Г = 4;", pad("    Г = 4;")));
+    assert_eq!(lines[8], format!("{} |     Д = 6;", pad("    Д = 5;")));
+    assert_eq!(
+        lines[9],
+        format!("{}   КонецПроцедуры", pad("КонецПроцедуры"))
+    );
+    assert_eq!(lines.len(), 10);
+}
+
+#[test]
+fn test_diff_json() {
+    let new = OLD.replace("Д = 5", "Д = 6");
+    let (differ, out) = diff(DiffFormat::Json, OLD, &new);
+    assert!(differ);
+    let json: serde_json::Value = serde_json::from_str(&out).unwrap();
+    assert_eq!(json["old"], "old.bsl");
+    let hunk = &json["hunks"][0];
+    assert_eq!(hunk["old_start"], 5);
+    assert_eq!(hunk["method"], "Процедура Первая()");
+    assert_eq!(hunk["lines"][3]["tag"], "delete");
+    assert_eq!(hunk["lines"][4]["text"], "    Д = 6;");
+
+    let (differ, out) = diff(DiffFormat::Json, OLD, OLD);
+    assert!(!differ);
+    let json: serde_json::Value = serde_json::from_str(&out).unwrap();
+    assert_eq!(json["hunks"].as_array().unwrap().len(), 0);
+}
+
+/// editor is not launched, exit code is 1 if files differ
+#[test]
+fn test_cmd_diff_output() {
+    let dir = tempdir().expect("tempdir");
+    let base_cfg = dir.path().join("base_cfg.txt");
+    let second_cfg = dir.path().join("second_cfg.txt");
+
+    for (second, code) in [("", 0), ("changed", 1)] {
+        let mut prog = prepare_diff(&base_cfg, &second_cfg, true, true);
+        if let Some(Action::Diff { output, .. }) = prog.action.as_mut() {
+            *output = Some(DiffFormat::Unified);
+        }
+        fs::write(&second_cfg, second).unwrap();
+
+        assert_eq!(prog.run().unwrap(), code);
+        assert!(prog.into_vscmd().files.is_empty());
+        // sources are not moved or removed
+        assert!(base_cfg.exists());
+        assert!(second_cfg.exists());
+    }
+}
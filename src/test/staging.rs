@@ -0,0 +1,242 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    process::ExitStatus,
+};
+
+use super::{prepare_merge, TestComand};
+use crate::staging::Transaction;
+use crate::*;
+use tempfile::tempdir;
+
+/// editor that can't be launched
+pub(super) struct FailingComand;
+
+impl WrappedCommand for FailingComand {
+    fn args<I, S>(&mut self, _args: I) -> &mut Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<std::ffi::OsStr>,
+    {
+        self
+    }
+
+    fn status(&mut self) -> io::Result<ExitStatus> {
+        Err(io::Error::new(io::ErrorKind::NotFound, "no editor"))
+    }
+
+    fn get_program(&self) -> &std::ffi::OsStr {
+        "no-editor".as_ref()
+    }
+}
+
+/// merge program with `vscmd` and files in `dir`
+fn merge_with<C: WrappedCommand>(dir: &Path, vscmd: C, remove: bool) -> Program<C> {
+    let prog = prepare_merge(
+        &dir.join("base_cfg.txt"),
+        &dir.join("second_cfg.txt"),
+        &dir.join("old_vendor_cfg.txt"),
+        &dir.join("merged.txt"),
+        false,
+        remove,
+        true,
+    );
+    let mut prog = Program::new_test(prog.action.unwrap(), vscmd, remove, true);
+    prog.staging_dir = Some(dir.join("staging"));
+    prog
+}
+
+/// check sources are in place with original content and nothing left in staging dir
+fn check_restored(dir: &Path) {
+    for name in ["base_cfg", "second_cfg", "old_vendor_cfg"] {
+        let content = fs::read_to_string(dir.join(format!("{name}.txt"))).unwrap();
+        assert_eq!(content, name);
+    }
+    assert_eq!(fs::read_dir(dir.join("staging")).unwrap().count(), 0);
+}
+
+#[test]
+fn test_transaction_rollback() {
+    let dir = tempdir().expect("tempdir");
+    let src = dir.path().join("src.txt");
+    let moved = dir.path().join("moved.bsl");
+    let copied = dir.path().join("copied.bsl");
+    let written = dir.path().join("written.bsl");
+    fs::write(&src, "src").unwrap();
+
+    let mut tx = Transaction::new();
+    tx.copy(&src, &copied).unwrap();
+    tx.move_file(&src, &moved).unwrap();
+    tx.write(&written, b"written").unwrap();
+    assert!(!src.exists());
+    drop(tx);
+
+    assert_eq!(fs::read_to_string(&src).unwrap(), "src");
+    assert!(!moved.exists());
+    assert!(!copied.exists());
+    assert!(!written.exists());
+}
+
+#[test]
+fn test_transaction_commit() {
+    let dir = tempdir().expect("tempdir");
+    let src = dir.path().join("src.txt");
+    let moved = dir.path().join("moved.bsl");
+    fs::write(&src, "src").unwrap();
+
+    let mut tx = Transaction::new();
+    tx.move_file(&src, &moved).unwrap();
+    tx.commit();
+
+    assert!(!src.exists());
+    assert!(moved.exists());
+}
+
+/// moved sources are restored if editor can't be launched, even with `--remove-files`
+#[test]
+fn test_rollback_editor_failure() {
+    for remove in [false, true] {
+        let dir = tempdir().expect("tempdir");
+        let mut prog = merge_with(dir.path(), FailingComand, remove);
+
+        assert!(prog.run().is_err());
+
+        check_restored(dir.path());
+        assert!(!dir.path().join("merged.txt").exists());
+    }
+}
+
+/// already moved sources are restored if staging of next one fails
+#[test]
+fn test_rollback_staging_failure() {
+    let dir = tempdir().expect("tempdir");
+    let mut prog = merge_with(dir.path(), TestComand::new(), false);
+    fs::remove_file(dir.path().join("old_vendor_cfg.txt")).unwrap();
+
+    assert!(prog.run().is_err());
+
+    for name in ["base_cfg", "second_cfg"] {
+        let content = fs::read_to_string(dir.path().join(format!("{name}.txt"))).unwrap();
+        assert_eq!(content, name);
+    }
+    assert_eq!(fs::read_dir(dir.path().join("staging")).unwrap().count(), 0);
+    // editor is not launched
+    assert!(prog.into_vscmd().files.is_empty());
+}
+
+/// moved sources are restored if merge result can't be written
+#[test]
+fn test_rollback_write_back_failure() {
+    let dir = tempdir().expect("tempdir");
+    let mut prog = merge_with(dir.path(), TestComand::new(), true);
+    let merged = dir.path().join("missing").join("merged.txt");
+    if let Some(Action::Merge { merged: m, .. }) = prog.action.as_mut() {
+        *m = merged.clone();
+    }
+
+    assert!(prog.run().is_err());
+
+    check_restored(dir.path());
+    assert!(!merged.exists());
+}
+
+/// editor that puts a directory in place of moved `base_cfg.txt` in `dir`, it can't be moved back
+struct BlockingComand {
+    dir: PathBuf,
+}
+
+impl WrappedCommand for BlockingComand {
+    fn args<I, S>(&mut self, _args: I) -> &mut Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<std::ffi::OsStr>,
+    {
+        self
+    }
+
+    fn status(&mut self) -> io::Result<ExitStatus> {
+        #[cfg(unix)]
+        use std::os::unix::process::ExitStatusExt;
+        #[cfg(windows)]
+        use std::os::windows::process::ExitStatusExt;
+        fs::create_dir(self.dir.join("base_cfg.txt"))?;
+        Ok(ExitStatus::from_raw(0))
+    }
+
+    fn get_program(&self) -> &std::ffi::OsStr {
+        "blocking".as_ref()
+    }
+}
+
+/// staging directory with moved source is kept if it can't be moved back
+#[test]
+fn test_rollback_failure_keeps_staging() {
+    let dir = tempdir().expect("tempdir");
+    let vscmd = BlockingComand {
+        dir: dir.path().to_path_buf(),
+    };
+    let mut prog = merge_with(dir.path(), vscmd, false);
+
+    let err = prog.run().unwrap_err();
+
+    let Error::StagingKept { dir: kept, source } = err else {
+        panic!("{err:?}");
+    };
+    assert!(matches!(*source, Error::RenameBack { .. }));
+    assert!(kept.starts_with(dir.path().join("staging")));
+    let staged = fs::read_to_string(kept.join("base_cfg.bsl")).unwrap();
+    assert_eq!(staged, "base_cfg");
+    // others are moved back
+    let second = fs::read_to_string(dir.path().join("second_cfg.txt")).unwrap();
+    assert_eq!(second, "second_cfg");
+}
+
+/// nothing is moved, written or launched in dry run
+#[test]
+fn test_dry_run() {
+    for auto_merge in [false, true] {
+        let dir = tempdir().expect("tempdir");
+        let mut prog = merge_with(dir.path(), TestComand::new(), true);
+        prog.dry_run = true;
+        prog.auto_merge = auto_merge;
+
+        assert_eq!(prog.run().unwrap(), 0);
+
+        for name in ["base_cfg", "second_cfg", "old_vendor_cfg"] {
+            let content = fs::read_to_string(dir.path().join(format!("{name}.txt"))).unwrap();
+            assert_eq!(content, name);
+        }
+        assert!(!dir.path().join("merged.txt").exists());
+        assert!(!dir.path().join("staging").exists());
+        assert!(prog.into_vscmd().files.is_empty());
+    }
+}
+
+#[test]
+fn test_transaction_dry_run() {
+    let dir = tempdir().expect("tempdir");
+    let src = dir.path().join("src.txt");
+    let moved = dir.path().join("moved.bsl");
+    fs::write(&src, "src").unwrap();
+
+    let mut tx = Transaction::dry_run();
+    tx.move_file(&src, &moved).unwrap();
+    tx.write(&dir.path().join("written.bsl"), b"written")
+        .unwrap();
+    tx.commit();
+
+    assert!(src.exists());
+    assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+}
+
+/// dry run prints editor args as they are, paths with spaces are quoted
+#[test]
+fn test_quote() {
+    let quoted = |arg: &str| crate::quote(std::ffi::OsStr::new(arg));
+    assert_eq!(quoted(r"C:\Temp\Модуль.bsl"), r"C:\Temp\Модуль.bsl");
+    assert_eq!(
+        quoted(r"C:\Program Files\code.cmd"),
+        r#""C:\Program Files\code.cmd""#
+    );
+    assert_eq!(quoted(""), r#""""#);
+}
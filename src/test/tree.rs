@@ -0,0 +1,308 @@
+use std::fs;
+use std::path::Path;
+
+use tempfile::tempdir;
+
+use super::TestComand;
+use crate::exit::*;
+use crate::tree::*;
+use crate::*;
+
+/// write `files` (relative path, content) into `root`
+fn write_tree(root: &Path, files: &[(&str, &str)]) {
+    for (path, content) in files {
+        let path = root.join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+}
+
+/// base, new vendor and old vendor trees in `dir`
+fn write_trees(dir: &Path) -> [PathBuf; 3] {
+    let trees = ["base", "new", "old"].map(|name| dir.join(name));
+    write_tree(
+        &trees[0],
+        &[
+            ("M/Ext/Module.bsl", "A\nb\nc\n"),
+            ("conf.xml", "x1\n"),
+            ("same.txt", "same\n"),
+            ("ours.txt", "ours\n"),
+        ],
+    );
+    write_tree(
+        &trees[1],
+        &[
+            ("M/Ext/Module.bsl", "a\nb\nC\n"),
+            ("conf.xml", "x2\n"),
+            ("same.txt", "same\n"),
+            ("ours.txt", "o\n"),
+            ("added.txt", "added\n"),
+            ("changed.txt", "changed\n"),
+        ],
+    );
+    write_tree(
+        &trees[2],
+        &[
+            ("M/Ext/Module.bsl", "a\nb\nc\n"),
+            ("conf.xml", "x\n"),
+            ("same.txt", "same\n"),
+            ("ours.txt", "o\n"),
+            ("removed.txt", "removed\n"),
+            ("changed.txt", "old\n"),
+        ],
+    );
+    trees
+}
+
+#[test]
+fn test_resolve() {
+    let [a, b, c] = [Some(&b"a"[..]), Some(&b"b"[..]), Some(&b"c"[..])];
+    assert_eq!(resolve(a, a, a), Resolution::Take(Some(Side::Base)));
+    assert_eq!(resolve(b, a, a), Resolution::Take(Some(Side::Base)));
+    assert_eq!(resolve(a, b, a), Resolution::Take(Some(Side::Second)));
+    assert_eq!(resolve(b, b, a), Resolution::Take(Some(Side::Base)));
+    assert_eq!(resolve(b, c, a), Resolution::Merge);
+    // added
+    assert_eq!(resolve(None, b, None), Resolution::Take(Some(Side::Second)));
+    assert_eq!(resolve(b, c, None), Resolution::Merge);
+    // removed
+    assert_eq!(resolve(None, a, a), Resolution::Take(None));
+    assert_eq!(resolve(a, None, a), Resolution::Take(None));
+    assert_eq!(resolve(None, None, a), Resolution::Take(None));
+    assert_eq!(resolve(None, b, a), Resolution::Conflict);
+    assert_eq!(resolve(b, None, a), Resolution::Conflict);
+}
+
+#[test]
+fn test_diff_trees() {
+    let dir = tempdir().expect("tempdir");
+    let [base, second, _] = write_trees(dir.path());
+    let entries = tree::diff(&base, &second).unwrap();
+    let changes: Vec<(String, Change)> = entries
+        .iter()
+        .map(|entry| {
+            (
+                entry.path.to_string_lossy().replace('\\', "/"),
+                entry.change,
+            )
+        })
+        .collect();
+    let expected = [
+        ("M/Ext/Module.bsl", Change::Changed),
+        ("added.txt", Change::Added),
+        ("changed.txt", Change::Added),
+        ("conf.xml", Change::Changed),
+        ("ours.txt", Change::Changed),
+        ("same.txt", Change::Identical),
+    ];
+    assert_eq!(
+        changes,
+        expected.map(|(path, change)| (path.to_string(), change))
+    );
+    assert_eq!(
+        tree::title(&base, Path::new("M/Ext/Module.bsl")),
+        "M.Ext.Module (base)"
+    );
+
+    let diff_dir = |list| Action::DiffDir {
+        base_dir: base.clone(),
+        second_dir: second.clone(),
+        list,
+    };
+    let mut prog = Program::builder(diff_dir(true), TestComand::new()).build();
+    assert_eq!(prog.run().unwrap(), EXIT_DIFFERENT);
+    assert!(prog.into_vscmd().files.is_empty());
+    // editor is opened for each changed file
+    let mut prog = Program::builder(diff_dir(false), TestComand::new()).build();
+    assert_eq!(prog.run().unwrap(), EXIT_SUCCESS);
+    let files = prog.into_vscmd().files;
+    assert_eq!(files.len(), 6);
+    assert_eq!(files[0].1, b"A\nb\nc\n");
+    assert_eq!(files[1].1, b"a\nb\nC\n");
+}
+
+#[test]
+fn test_merge_trees() {
+    let dir = tempdir().expect("tempdir");
+    let [base, second, old_vendor] = write_trees(dir.path());
+    let entries = tree::merge(&base, &second, &old_vendor).unwrap();
+    let resolutions: Vec<(&str, Resolution)> = entries
+        .iter()
+        .map(|entry| {
+            (
+                entry.path.file_name().unwrap().to_str().unwrap(),
+                entry.resolution,
+            )
+        })
+        .collect();
+    assert_eq!(
+        resolutions,
+        [
+            ("Module.bsl", Resolution::Merge),
+            ("added.txt", Resolution::Take(Some(Side::Second))),
+            ("changed.txt", Resolution::Conflict),
+            ("conf.xml", Resolution::Merge),
+            ("ours.txt", Resolution::Take(Some(Side::Base))),
+            ("removed.txt", Resolution::Take(None)),
+            ("same.txt", Resolution::Take(Some(Side::Base))),
+        ]
+    );
+    assert_eq!(
+        (entries[2].ours, entries[2].theirs),
+        (Change::Removed, Change::Changed)
+    );
+}
+
+#[test]
+fn test_merge_dir() {
+    let dir = tempdir().expect("tempdir");
+    let [base, second, old_vendor] = write_trees(dir.path());
+    let merged = dir.path().join("merged");
+    let merge_dir = |workspace| Action::MergeDir {
+        base_dir: base.clone(),
+        second_dir: second.clone(),
+        old_vendor_dir: old_vendor.clone(),
+        merged_dir: merged.clone(),
+        from_second: false,
+        workspace,
+        journal: None,
+    };
+
+    // files changed on both sides are opened one by one
+    let mut prog = Program::builder(merge_dir(false), TestComand::new()).build();
+    assert_eq!(prog.run().unwrap(), EXIT_CONFLICTS);
+    let files = prog.into_vscmd().files;
+    assert_eq!(files.len(), 8);
+    assert_eq!(fs::read(merged.join("added.txt")).unwrap(), b"added\n");
+    assert_eq!(fs::read(merged.join("ours.txt")).unwrap(), b"ours\n");
+    assert_eq!(fs::read(merged.join("same.txt")).unwrap(), b"same\n");
+    assert_eq!(fs::read(merged.join("conf.xml")).unwrap(), b"x1\n");
+    assert!(!merged.join("removed.txt").exists());
+    assert!(!merged.join("changed.txt").exists());
+
+    // lines are merged, conflicts are left in one editor window
+    fs::remove_dir_all(&merged).unwrap();
+    let mut prog = Program::builder(merge_dir(true), TestComand::new())
+        .auto_merge(true)
+        .build();
+    assert_eq!(prog.run().unwrap(), EXIT_CONFLICTS);
+    assert!(prog.into_vscmd().files.is_empty());
+    let module = fs::read_to_string(merged.join("M/Ext/Module.bsl")).unwrap();
+    assert_eq!(module, "A\nb\nC\n");
+    let conf = fs::read_to_string(merged.join("conf.xml")).unwrap();
+    assert!(conf.starts_with("<<<<<<< conf (base)\nx1\n"), "{conf}");
+
+    // without `--auto-merge` all files are opened, starting from base ones
+    fs::remove_dir_all(&merged).unwrap();
+    let mut prog = Program::builder(merge_dir(true), TestComand::new()).build();
+    assert_eq!(prog.run().unwrap(), EXIT_CONFLICTS);
+    let module = fs::read_to_string(merged.join("M/Ext/Module.bsl")).unwrap();
+    assert_eq!(module, "A\nb\nc\n");
+    assert_eq!(fs::read(merged.join("conf.xml")).unwrap(), b"x1\n");
+
+    // merged into base tree
+    let mut prog = Program::builder(merge_dir(true), TestComand::new()).build();
+    prog.action = Some(Action::MergeDir {
+        base_dir: base.clone(),
+        second_dir: second.clone(),
+        old_vendor_dir: old_vendor.clone(),
+        merged_dir: base.clone(),
+        from_second: false,
+        workspace: true,
+        journal: None,
+    });
+    prog.run().unwrap();
+    assert_eq!(fs::read(base.join("added.txt")).unwrap(), b"added\n");
+    assert_eq!(fs::read(base.join("ours.txt")).unwrap(), b"ours\n");
+}
+
+#[test]
+fn test_merge_dir_dry_run() {
+    let dir = tempdir().expect("tempdir");
+    let [base, second, old_vendor] = write_trees(dir.path());
+    let merged = dir.path().join("merged");
+    let action = Action::MergeDir {
+        base_dir: base,
+        second_dir: second,
+        old_vendor_dir: old_vendor,
+        merged_dir: merged.clone(),
+        from_second: false,
+        workspace: true,
+        journal: None,
+    };
+    let mut prog = Program::builder(action, TestComand::new())
+        .dry_run(true)
+        .build();
+    prog.run().unwrap();
+    assert!(prog.into_vscmd().files.is_empty());
+    assert!(!merged.exists());
+}
+
+/// file added on both sides is merged with empty ancestor staged with the others, nothing is
+/// created in dry run
+#[test]
+fn test_merge_dir_added_on_both_sides() {
+    let dir = tempdir().expect("tempdir");
+    let trees = ["base", "new", "old"].map(|name| dir.path().join(name));
+    write_tree(&trees[0], &[("added.txt", "ours\n")]);
+    write_tree(&trees[1], &[("added.txt", "theirs\n")]);
+    fs::create_dir(&trees[2]).unwrap();
+    let staging = dir.path().join("staging");
+    let [base, second, old_vendor] = trees;
+    let action = || Action::MergeDir {
+        base_dir: base.clone(),
+        second_dir: second.clone(),
+        old_vendor_dir: old_vendor.clone(),
+        merged_dir: dir.path().join("merged"),
+        from_second: false,
+        workspace: false,
+        journal: None,
+    };
+
+    let mut prog = Program::builder(action(), TestComand::new())
+        .staging_dir(Some(staging.clone()))
+        .dry_run(true)
+        .build();
+    assert_eq!(prog.run().unwrap(), EXIT_SUCCESS);
+    assert!(prog.into_vscmd().files.is_empty());
+    assert!(!staging.exists());
+    assert!(!dir.path().join("merged").exists());
+
+    let mut prog = Program::builder(action(), TestComand::new())
+        .staging_dir(Some(staging.clone()))
+        .build();
+    assert_eq!(prog.run().unwrap(), EXIT_SUCCESS);
+    let files = prog.into_vscmd().files;
+    let contents: Vec<&[u8]> = files.iter().map(|(_, content)| &content[..]).collect();
+    assert_eq!(contents, [&b"ours\n"[..], b"theirs\n", b"", b"ours\n"]);
+    assert_eq!(fs::read_dir(&staging).unwrap().count(), 0);
+}
+
+/// with `--auto-merge` files are merged by lines once, conflicts are the initial result in editor
+#[test]
+fn test_merge_dir_auto_merge_one_by_one() {
+    let dir = tempdir().expect("tempdir");
+    let [base, second, old_vendor] = write_trees(dir.path());
+    let merged = dir.path().join("merged");
+    let action = Action::MergeDir {
+        base_dir: base,
+        second_dir: second,
+        old_vendor_dir: old_vendor,
+        merged_dir: merged.clone(),
+        from_second: false,
+        workspace: false,
+        journal: None,
+    };
+    let mut prog = Program::builder(action, TestComand::new())
+        .auto_merge(true)
+        .build();
+    assert_eq!(prog.run().unwrap(), EXIT_CONFLICTS);
+
+    let module = fs::read_to_string(merged.join("M/Ext/Module.bsl")).unwrap();
+    assert_eq!(module, "A\nb\nC\n");
+    // conf.xml only
+    let files = prog.into_vscmd().files;
+    assert_eq!(files.len(), 4);
+    let seed = String::from_utf8_lossy(&files[3].1).into_owned();
+    assert!(seed.contains("<<<<<<< conf (base)\nx1\n"), "{seed}");
+}
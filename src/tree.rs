@@ -0,0 +1,175 @@
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::error::Error;
+use crate::utils::read_file;
+
+/// change of file from old tree to new one
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Change {
+    Added,
+    Removed,
+    Changed,
+    /// same content, or absent in both trees
+    Identical,
+}
+
+impl Change {
+    /// change from `old` to `new` content, `None` if file is absent
+    pub fn between(old: Option<&[u8]>, new: Option<&[u8]>) -> Self {
+        match (old, new) {
+            (None, Some(_)) => Change::Added,
+            (Some(_), None) => Change::Removed,
+            (Some(old), Some(new)) if old != new => Change::Changed,
+            _ => Change::Identical,
+        }
+    }
+
+    /// mark in listing of trees (like `git status --short`)
+    pub fn mark(&self) -> char {
+        match self {
+            Change::Added => 'A',
+            Change::Removed => 'D',
+            Change::Changed => 'M',
+            Change::Identical => '=',
+        }
+    }
+}
+
+/// tree of merge a resolved file is taken from
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Side {
+    /// %baseCfg (ours)
+    Base,
+    /// %secondCfg (theirs)
+    Second,
+}
+
+/// how file of merged trees gets into result
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Resolution {
+    /// same on both sides or changed on one side only: file of `Side` as is, `None` if removed
+    Take(Option<Side>),
+    /// changed (or added) differently on both sides: merged by lines or in editor
+    Merge,
+    /// removed on one side and changed on the other, left to user
+    Conflict,
+}
+
+/// file of 2 compared trees
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DiffEntry {
+    /// path relative to tree roots
+    pub path: PathBuf,
+    /// from base tree to second
+    pub change: Change,
+}
+
+/// file of 3 merged trees
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MergeEntry {
+    /// path relative to tree roots
+    pub path: PathBuf,
+    /// from old vendor tree to base
+    pub ours: Change,
+    /// from old vendor tree to second
+    pub theirs: Change,
+    pub resolution: Resolution,
+}
+
+impl MergeEntry {
+    /// file is not changed on any side
+    pub fn is_identical(&self) -> bool {
+        self.ours == Change::Identical && self.theirs == Change::Identical
+    }
+}
+
+/// title of file at relative `path` in tree `root` for editor: path without extension and tree name
+pub fn title(root: &Path, path: &Path) -> String {
+    let stem = path.with_extension("");
+    let parts: Vec<_> = stem.iter().map(|part| part.to_string_lossy()).collect();
+    let tree = root.file_name().unwrap_or_default().to_string_lossy();
+    format!("{} ({tree})", parts.join("."))
+}
+
+/// relative paths of all files in `root` and its subdirectories
+pub fn files(root: &Path) -> Result<BTreeSet<PathBuf>, Error> {
+    let mut files = BTreeSet::new();
+    let mut dirs = vec![PathBuf::new()];
+    while let Some(dir) = dirs.pop() {
+        let error = |source| Error::Read {
+            path: root.join(&dir),
+            source,
+        };
+        for entry in fs::read_dir(root.join(&dir)).map_err(error)? {
+            let entry = entry.map_err(error)?;
+            let path = dir.join(entry.file_name());
+            if entry.file_type().map_err(error)?.is_dir() {
+                dirs.push(path);
+            } else {
+                files.insert(path);
+            }
+        }
+    }
+    Ok(files)
+}
+
+/// content of `root`/`path`, `None` if the tree has no such file
+fn content(root: &Path, files: &BTreeSet<PathBuf>, path: &Path) -> Result<Option<Vec<u8>>, Error> {
+    if files.contains(path) {
+        read_file(&root.join(path)).map(Some)
+    } else {
+        Ok(None)
+    }
+}
+
+/// files of `base` and `second` trees paired by relative path, sorted
+pub fn diff(base: &Path, second: &Path) -> Result<Vec<DiffEntry>, Error> {
+    let base_files = files(base)?;
+    let second_files = files(second)?;
+    let mut entries = vec![];
+    for path in base_files.union(&second_files) {
+        let old = content(base, &base_files, path)?;
+        let new = content(second, &second_files, path)?;
+        entries.push(DiffEntry {
+            path: path.clone(),
+            change: Change::between(old.as_deref(), new.as_deref()),
+        });
+    }
+    Ok(entries)
+}
+
+/// files of `base`, `second` and `old_vendor` trees paired by relative path, sorted
+pub fn merge(base: &Path, second: &Path, old_vendor: &Path) -> Result<Vec<MergeEntry>, Error> {
+    let trees = [base, second, old_vendor];
+    let files = [files(base)?, files(second)?, files(old_vendor)?];
+    let all: BTreeSet<&PathBuf> = files.iter().flatten().collect();
+    let mut entries = vec![];
+    for path in all {
+        let [ours, theirs, ancestor] = [0, 1, 2].map(|i| content(trees[i], &files[i], path));
+        let [ours, theirs, ancestor] = [ours?, theirs?, ancestor?];
+        let [ours, theirs, ancestor] = [ours.as_deref(), theirs.as_deref(), ancestor.as_deref()];
+        entries.push(MergeEntry {
+            path: path.clone(),
+            ours: Change::between(ancestor, ours),
+            theirs: Change::between(ancestor, theirs),
+            resolution: resolve(ours, theirs, ancestor),
+        });
+    }
+    Ok(entries)
+}
+
+/// resolution of file with `ours`, `theirs` and `ancestor` content (`None` if absent)
+pub fn resolve(ours: Option<&[u8]>, theirs: Option<&[u8]>, ancestor: Option<&[u8]>) -> Resolution {
+    let side = |content: Option<&[u8]>, side| content.map(|_| side);
+    if theirs == ancestor || ours == theirs {
+        Resolution::Take(side(ours, Side::Base))
+    } else if ours == ancestor {
+        Resolution::Take(side(theirs, Side::Second))
+    } else if ours.is_none() || theirs.is_none() {
+        Resolution::Conflict
+    } else {
+        Resolution::Merge
+    }
+}
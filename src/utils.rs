@@ -1,16 +1,16 @@
-use anyhow::Result;
-use std::ffi::OsStr;
+use log::{error, info};
+use std::ffi::{OsStr, OsString};
+use std::hash::{DefaultHasher, Hash, Hasher};
 use std::io;
+use std::path::Path;
 use std::process::ExitStatus;
+use std::time::Instant;
 use std::{fs, path::PathBuf, process};
-use which::which;
 
-use lazy_static::lazy_static;
-
-lazy_static! {
-    /// command to launch VSCode
-    pub static ref CODE_CMD: PathBuf = which("code").expect(r#"no "code" found in path"#);
-}
+use crate::encoding::{self, TextEncoding};
+use crate::error::Error;
+use crate::paths::sanitize_file_name;
+use crate::staging::{move_file, Transaction, Workspace};
 
 pub trait WrappedCommand {
     fn args<I, S>(&mut self, args: I) -> &mut Self
@@ -18,21 +18,27 @@ pub trait WrappedCommand {
         I: IntoIterator<Item = S>,
         S: AsRef<OsStr>;
     fn status(&mut self) -> io::Result<ExitStatus>;
+    /// forget args of previous launch: `diff-dir` and `merge-dir` launch editor for each file,
+    /// default does nothing (for commands launched once)
+    fn clear_args(&mut self) {}
+    /// path to editor
+    fn get_program(&self) -> &OsStr;
 }
 
-pub struct VSCodeComand {
+/// command to launch editor
+pub struct EditorCommand {
     inner: process::Command,
 }
 
-impl VSCodeComand {
-    pub fn new() -> Self {
+impl EditorCommand {
+    pub fn new<S: AsRef<OsStr>>(program: S) -> Self {
         Self {
-            inner: process::Command::new(CODE_CMD.as_os_str()),
+            inner: process::Command::new(program),
         }
     }
 }
 
-impl WrappedCommand for VSCodeComand {
+impl WrappedCommand for EditorCommand {
     fn args<I, S>(&mut self, args: I) -> &mut Self
     where
         I: IntoIterator<Item = S>,
@@ -43,35 +49,101 @@ impl WrappedCommand for VSCodeComand {
     }
 
     fn status(&mut self) -> io::Result<ExitStatus> {
-        self.inner.status()
+        info!("launch {:?}", self.inner);
+        let started = Instant::now();
+        let status = self.inner.status();
+        match &status {
+            Ok(status) => info!(
+                "editor exited with {status} after {:.1?}",
+                started.elapsed()
+            ),
+            Err(err) => error!("can't launch editor: {err}"),
+        }
+        status
+    }
+
+    fn clear_args(&mut self) {
+        self.inner = process::Command::new(self.inner.get_program());
+    }
+
+    fn get_program(&self) -> &OsStr {
+        self.inner.get_program()
     }
 }
 
-/// copy/rename mutable slice of files (set extension)
+/// copy/move mutable slice of files into `workspace` (set extension, file name from title if any),
+/// files not in UTF-8 are converted (and never moved), returns source encodings
 pub fn set_ext_to_all(
     files: &mut [&mut PathBuf],
+    titles: &[Option<String>],
+    workspace: &Workspace,
+    tx: &mut Transaction,
     extension: &str,
     rename_files: bool,
-) -> Result<()> {
-    for file in files.iter_mut() {
+) -> Result<Vec<TextEncoding>, Error> {
+    let mut encodings = vec![];
+    for (i, file) in files.iter_mut().enumerate() {
         let src = file.clone();
-        file.set_extension(extension);
-        if src.extension() == file.extension() {
-            continue;
-        }
-        if rename_files {
-            fs::rename(src, &file)?;
-        } else {
-            fs::copy(src, &file)?;
-        }
+        let title = titles.get(i).cloned().flatten();
+        let stem = match title.as_deref().and_then(sanitize_file_name) {
+            Some(name) => OsString::from(name),
+            None => src.file_stem().unwrap_or_default().to_os_string(),
+        };
+        // new path in workspace (never `src`): sources are not converted in place
+        **file = workspace.file_path(&stem, extension);
+        let (encoding, converted) = encoding::to_editor(&read_file(&src)?);
+        encodings.push(encoding);
+        let staged = match converted {
+            Some(converted) => tx.write(file, &converted),
+            None if rename_files => tx.move_file(&src, file),
+            None => tx.copy(&src, file),
+        };
+        staged.map_err(|source| Error::Stage {
+            from: src,
+            to: file.to_path_buf(),
+            source,
+        })?;
     }
-    Ok(())
+    Ok(encodings)
+}
+
+/// content of file, error with its path
+pub fn read_file(path: &Path) -> Result<Vec<u8>, Error> {
+    fs::read(path).map_err(|source| Error::Read {
+        path: path.to_path_buf(),
+        source,
+    })
+}
+
+/// text of file decoded from detected encoding, and the encoding
+pub fn read_text(path: &Path) -> Result<(String, TextEncoding), Error> {
+    let bytes = read_file(path)?;
+    let encoding = encoding::detect(&bytes);
+    Ok((encoding::decode(&bytes, encoding), encoding))
+}
+
+/// hash of file content to check if it was changed
+pub fn file_hash(path: &Path) -> Result<u64, Error> {
+    let mut hasher = DefaultHasher::new();
+    read_file(path)?.hash(&mut hasher);
+    Ok(hasher.finish())
 }
 
-/// remove files
-pub fn remove_all_files<'a, I: Iterator<Item = &'a mut PathBuf>>(files: I) -> Result<()> {
-    for file in files {
-        fs::remove_file(file)?
+/// move `src` (edited in UTF-8) to `dst` in `encoding`
+pub fn write_back(src: &Path, dst: &Path, encoding: TextEncoding) -> Result<(), Error> {
+    let bytes = read_file(src)?;
+    let detected = encoding::detect(&bytes);
+    let error = |source| Error::WriteBack {
+        path: dst.to_path_buf(),
+        source,
+    };
+    if detected == encoding {
+        return move_file(src, dst).map_err(error);
     }
-    Ok(())
+    let text = encoding::decode(&bytes, detected);
+    fs::write(dst, encoding::encode(&text, encoding)).map_err(error)?;
+    fs::remove_file(src).map_err(|source| Error::Cleanup {
+        path: src.to_path_buf(),
+        source,
+    })
 }